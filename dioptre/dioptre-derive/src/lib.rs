@@ -1,9 +1,9 @@
 extern crate proc_macro;
 
-use syn::{Data, DeriveInput, Error, Fields, parse_macro_input};
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Meta, NestedMeta, parse_macro_input};
 use quote::quote;
 
-#[proc_macro_derive(Fields)]
+#[proc_macro_derive(Fields, attributes(dioptre))]
 pub fn fields_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let ast: syn::DeriveInput = parse_macro_input!(input as DeriveInput);
     let ident = &ast.ident;
@@ -23,41 +23,92 @@ pub fn fields_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream
         }
     }
 
-    let offsets = data.fields.iter().map(|field| &field.ident);
-    let sizes = data.fields.iter().map(|field| &field.ty);
-    let aligns = data.fields.iter().map(|field| &field.ty);
+    // Flattened fields are followed by their own type's elements, so indices are computed in
+    // terms of those types' element counts.
+    let mut len = quote! { 0usize };
+    let (mut offsets, mut sizes, mut aligns, mut parents) = (vec![], vec![], vec![], vec![]);
+    let mut consts = vec![];
+    for field in data.fields.iter() {
+        let flatten = match is_flattened(&field.attrs) {
+            Ok(flatten) => flatten,
+            Err(e) => return proc_macro::TokenStream::from(e.to_compile_error()),
+        };
+        let (vis, field, ty) = (&field.vis, &field.ident, &field.ty);
+        let index = len.clone();
 
-    let vis = data.fields.iter().map(|field| &field.vis);
-    let field = data.fields.iter().map(|field| &field.ident);
-    let ty = data.fields.iter().map(|field| &field.ty);
-    let index = 0..data.fields.iter().count();
+        offsets.push(quote! { &[|object| unsafe {
+            let field = ::core::ptr::addr_of_mut!((*(object as *mut Self)).#field);
+            (field as usize) - (object as usize)
+        }] });
+        sizes.push(quote! { &[::core::mem::size_of::<#ty>()] });
+        aligns.push(quote! { &[::core::mem::align_of::<#ty>()] });
+        parents.push(quote! { &[::core::option::Option::None] });
+        consts.push(quote! {
+            #vis const #field: ::dioptre::Field<Self, #ty> = unsafe {
+                ::dioptre::Field::new(#index)
+            };
+        });
+
+        len = quote! { #len + 1 };
+        if flatten {
+            let nested = quote! { <#ty as ::dioptre::Fields>::SIZES.len() };
+            offsets.push(quote! { <#ty as ::dioptre::Fields>::OFFSETS });
+            sizes.push(quote! { <#ty as ::dioptre::Fields>::SIZES });
+            aligns.push(quote! { <#ty as ::dioptre::Fields>::ALIGNS });
+            parents.push(quote! {
+                &::dioptre::__private::nest::<{ #nested }>(
+                    <#ty as ::dioptre::Fields>::PARENTS, #index
+                )
+            });
+            len = quote! { #len + #nested };
+        }
+    }
 
     let expanded = quote! {
         unsafe impl #impl_generics ::dioptre::Fields for #ident #ty_generics #where_clause {
-            const OFFSETS: &'static [fn(*mut u8) -> usize] = &[
-                #(|object| unsafe {
-                    let #ident { #offsets: ref field, .. } = *(object as *mut Self);
-                    let offset = (field as *const _ as usize) - (object as *const _ as usize);
-                    offset
-                },)*
-            ];
-
-            const SIZES: &'static [usize] = &[
-                #(::core::mem::size_of::<#sizes>(),)*
-            ];
-
-            const ALIGNS: &'static [usize] = &[
-                #(::core::mem::align_of::<#aligns>(),)*
-            ];
+            const OFFSETS: &'static [fn(*mut u8) -> usize] = &::dioptre::__private::concat::<
+                fn(*mut u8) -> usize, { #len }
+            >(&[#(#offsets,)*]);
+
+            const SIZES: &'static [usize] =
+                &::dioptre::__private::concat::<usize, { #len }>(&[#(#sizes,)*]);
+
+            const ALIGNS: &'static [usize] =
+                &::dioptre::__private::concat::<usize, { #len }>(&[#(#aligns,)*]);
+
+            const PARENTS: &'static [::core::option::Option<usize>] =
+                &::dioptre::__private::concat::<::core::option::Option<usize>, { #len }>(
+                    &[#(#parents,)*]
+                );
         }
 
         #[allow(non_upper_case_globals)]
         impl #impl_generics #ident #ty_generics #where_clause {
-            #(#vis const #field: ::dioptre::Field<Self, #ty> = unsafe {
-                ::dioptre::Field::new(#index)
-            };)*
+            #(#consts)*
         }
     };
 
     proc_macro::TokenStream::from(expanded)
 }
+
+/// Check for `#[dioptre(flatten)]` among a field's attributes.
+fn is_flattened(attrs: &[Attribute]) -> Result<bool, Error> {
+    let mut flatten = false;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("dioptre")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new_spanned(meta, "expected `#[dioptre(...)]`")),
+        };
+
+        for nested in list.nested.iter() {
+            match *nested {
+                NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("flatten") => {
+                    flatten = true;
+                }
+                _ => return Err(Error::new_spanned(nested, "unknown `dioptre` option")),
+            }
+        }
+    }
+
+    Ok(flatten)
+}
@@ -30,7 +30,7 @@ impl<S: Fields> CellExt for Cell<S> {
 
     fn project<T>(&self, field: Field<S, T>) -> &Cell<T> {
         unsafe {
-            let offset = S::OFFSETS[field.index()](self.as_ptr() as *mut _);
+            let offset = crate::offset::<S>(field.index(), self.as_ptr() as *mut _);
             let field = (self as *const _ as *const u8).add(offset);
             &*(field as *const Cell<T>)
         }
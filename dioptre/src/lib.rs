@@ -21,21 +21,68 @@ pub mod ext;
 /// This trait should not normally be implemented by hand. Instead, use `#[derive(Fields)]`- this
 /// will safely generate the appropriate trait impl as well as associated const [`Field`]s.
 ///
+/// A field whose type also implements `Fields` can be marked `#[dioptre(flatten)]`. The field is
+/// then followed by its own type's elements, so a struct's elements are listed in pre-order, and
+/// its handle can be composed with its type's handles using [`Field::then`]:
+///
+/// ```
+/// use dioptre::Fields;
+///
+/// #[derive(Fields)]
+/// struct Vec2 {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// #[derive(Fields)]
+/// struct Body {
+///     #[dioptre(flatten)]
+///     position: Vec2,
+///     mass: f32,
+/// }
+///
+/// assert_eq!(Body::position.index(), 0);
+/// assert_eq!(Body::position.then(Vec2::y).index(), 2);
+/// assert_eq!(Body::mass.index(), 3);
+/// ```
+///
 /// # Safety
 ///
-/// * `OFFSETS`, `SIZES` and `ALIGNS` must accurately describe `Self`'s fields.
+/// * `OFFSETS`, `SIZES`, `ALIGNS` and `PARENTS` must accurately describe `Self`'s elements.
+/// * `OFFSETS` must not read from the object they are given.
+/// * A flattened element must be immediately followed by the elements of its type's own `Fields`
+///   impl, in order, with `PARENTS` adjusted to match.
 pub unsafe trait Fields {
-    /// The offsets of individual struct elements.
+    /// The offsets of individual struct elements, relative to their parents. Use [`offset`] to
+    /// get an element's offset from the start of the struct.
     //
     // These are encoded as functions because Rust has no sound way to compute field offsets ahead
-    // of time, without a valid object.
-    // Further, the parameter is a `*mut u8` instead of a `&Self`, despite requiring a valid
-    // object, because Rust has no way to express the type `for<'a> fn(&'a Self) where Self: 'a`.
+    // of time, without an object.
+    // The parameter is a `*mut u8` instead of a `&Self` because it need not point to an
+    // initialized object- only to memory with the size and alignment of `Self`.
     const OFFSETS: &'static [fn(*mut u8) -> usize];
     /// The sizes of individual struct elements.
     const SIZES: &'static [usize];
     /// The alignments of individual struct elements.
     const ALIGNS: &'static [usize];
+    /// The index of the flattened element containing each element, if any.
+    const PARENTS: &'static [Option<usize>];
+}
+
+/// Get the offset of element `index` of `S`, from the start of the object at `object`.
+///
+/// `object` need not point to an initialized object- only to memory with the size and alignment
+/// of `S`.
+///
+/// # Panics
+///
+/// Panics if `index` is out of bounds.
+pub fn offset<S: Fields>(index: usize, object: *mut u8) -> usize {
+    let base = match S::PARENTS[index] {
+        Some(parent) => offset::<S>(parent, object),
+        None => 0,
+    };
+    base + S::OFFSETS[index](object.wrapping_add(base))
 }
 
 /// A handle to a field in struct `S` of type `F`.
@@ -47,9 +94,75 @@ pub struct Field<S, F> {
 }
 
 impl<S, F> Field<S, F> {
+    /// Create a handle to the field at `index`.
+    ///
+    /// # Safety
+    ///
+    /// `index` must refer to a field of `S` with type `F`.
     pub const unsafe fn new(index: usize) -> Field<S, F> {
         Field { index, _marker: PhantomData }
     }
 
     pub const fn index(self) -> usize { self.index }
 }
+
+impl<S: Fields, F: Fields> Field<S, F> {
+    /// Compose with a handle to a field of `F`, to address that field within a flattened `F`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this field is not flattened.
+    pub const fn then<G>(self, field: Field<F, G>) -> Field<S, G> {
+        let first = self.index + 1;
+        let flattened = first < S::PARENTS.len()
+            && matches!(S::PARENTS[first], Some(parent) if parent == self.index);
+        assert!(flattened, "field is not flattened");
+        unsafe { Field::new(first + field.index) }
+    }
+}
+
+/// Helpers for code generated by `#[derive(Fields)]`.
+#[doc(hidden)]
+pub mod __private {
+    use core::mem::MaybeUninit;
+
+    /// Concatenate `parts` into an array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parts do not add up to `N` elements.
+    pub const fn concat<T: Copy, const N: usize>(parts: &[&[T]]) -> [T; N] {
+        let mut array = [MaybeUninit::<T>::uninit(); N];
+        let (mut len, mut part) = (0, 0);
+        while part < parts.len() {
+            let mut index = 0;
+            while index < parts[part].len() {
+                assert!(len < N, "too many elements");
+                array[len] = MaybeUninit::new(parts[part][index]);
+                len += 1;
+                index += 1;
+            }
+            part += 1;
+        }
+        assert!(len == N, "too few elements");
+
+        unsafe { *(&array as *const [MaybeUninit<T>; N] as *const [T; N]) }
+    }
+
+    /// Adjust the `PARENTS` of a flattened field's type, for elements that follow the field at
+    /// index `parent`.
+    pub const fn nest<const N: usize>(parents: &[Option<usize>], parent: usize)
+        -> [Option<usize>; N]
+    {
+        let mut array = [None; N];
+        let mut index = 0;
+        while index < N {
+            array[index] = match parents[index] {
+                Some(grandparent) => Some(parent + 1 + grandparent),
+                None => Some(parent),
+            };
+            index += 1;
+        }
+        array
+    }
+}
@@ -18,3 +18,50 @@ fn project() {
     assert_eq!(data.x, 8);
     assert_eq!(data.y, 13);
 }
+
+#[derive(Fields)]
+struct Vec2 {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Fields)]
+struct Body {
+    mass: f32,
+    #[dioptre(flatten)]
+    position: Vec2,
+    velocity: Vec2,
+}
+
+#[derive(Fields)]
+struct Scene {
+    #[dioptre(flatten)]
+    body: Body,
+    id: u32,
+}
+
+#[test]
+fn flatten() {
+    assert_eq!(Body::SIZES.len(), 5);
+    assert_eq!(Body::PARENTS, &[None, None, Some(1), Some(1), None]);
+    assert_eq!(Scene::PARENTS, &[None, Some(0), Some(0), Some(2), Some(2), Some(0), None]);
+    assert_eq!(Scene::body.then(Body::position).then(Vec2::y).index(), 4);
+    assert_eq!(Scene::id.index(), 6);
+
+    let (position, velocity) = (Vec2 { x: 2.0, y: 3.0 }, Vec2 { x: 4.0, y: 5.0 });
+    let mut scene = Scene { body: Body { mass: 1.0, position, velocity }, id: 6 };
+    let cell = Cell::from_mut(&mut scene);
+    cell.project(Scene::body.then(Body::position).then(Vec2::y)).set(7.0);
+    cell.project(Scene::body.then(Body::velocity)).set(Vec2 { x: 8.0, y: 9.0 });
+    cell.project(Scene::id).set(10);
+
+    assert_eq!(scene.body.position.y, 7.0);
+    assert_eq!(scene.body.velocity.y, 9.0);
+    assert_eq!(scene.id, 10);
+}
+
+#[test]
+#[should_panic(expected = "field is not flattened")]
+fn then_unflattened() {
+    Body::velocity.then(Vec2::x);
+}
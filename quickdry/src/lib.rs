@@ -138,7 +138,7 @@ impl Arena {
         let padded = layout.size() + layout.align() - 1;
         if padded > SLAB_SIZE {
             let next = self.alloc_slab(padded);
-            if next == ptr::null_mut() { return ptr::null_mut(); }
+            if next.is_null() { return ptr::null_mut(); }
 
             let offset = align_offset(next as usize, layout.align());
             return next.add(offset);
@@ -149,7 +149,7 @@ impl Arena {
         let size = SLAB_SIZE * (1 << cmp::min(30, slabs.len() / 128));
 
         let next = self.alloc_slab(size);
-        if next == ptr::null_mut() { return ptr::null_mut(); }
+        if next.is_null() { return ptr::null_mut(); }
 
         let offset = align_offset(next as usize, layout.align());
         let ptr = next.add(offset);
@@ -164,7 +164,7 @@ impl Arena {
         let slabs = &mut *self.slabs.get();
 
         let next = alloc(Layout::from_size_align_unchecked(size, 1));
-        if next == ptr::null_mut() { return ptr::null_mut(); }
+        if next.is_null() { return ptr::null_mut(); }
 
         // Save the slab and its size for drop.
         let slab = Box::from_raw(ptr::slice_from_raw_parts_mut(next, size));
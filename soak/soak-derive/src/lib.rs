@@ -1,9 +1,11 @@
 extern crate proc_macro;
 
-use syn::{Data, DeriveInput, Error, parse_macro_input};
-use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Error, Field, GenericArgument, GenericParam, Lifetime};
+use syn::{LifetimeDef, Lit, Meta, NestedMeta, PathArguments, Type, parse_macro_input};
+use quote::{format_ident, quote};
+use proc_macro2::Span;
 
-#[proc_macro_derive(Columns)]
+#[proc_macro_derive(Columns, attributes(soak))]
 pub fn columns_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let ast: syn::DeriveInput = parse_macro_input!(input as DeriveInput);
     let ident = &ast.ident;
@@ -16,18 +18,435 @@ pub fn columns_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream
         }
     };
 
-    let pointers = data.fields.iter().count();
-    let dangling = data.fields.iter().map(|field| &field.ty);
+    let options = match ContainerOptions::parse(&ast.attrs) {
+        Ok(options) => options,
+        Err(e) => return proc_macro::TokenStream::from(e.to_compile_error()),
+    };
+    let align = options.align.map(|align| quote! { const ALIGN: usize = #align; });
+
+    let options = data.fields.iter().map(FieldOptions::parse).collect::<Result<Vec<_>, _>>();
+    let options = match options {
+        Ok(options) => options,
+        Err(e) => return proc_macro::TokenStream::from(e.to_compile_error()),
+    };
+
+    // Each field's values go in the next array, or the next several arrays for a flattened field,
+    // and any validity bitsets follow.
+    let mut len = quote! { 0usize };
+    for (field, options) in Iterator::zip(data.fields.iter(), options.iter()) {
+        let ty = &field.ty;
+        len = match options.flatten {
+            true => quote! { #len + <#ty as ::soak::Columns>::ARRAYS.len() },
+            false => quote! { #len + 1 },
+        };
+    }
+
+    let lifetime = Lifetime::new("'soak", Span::call_site());
+    let mut bitsets = 0usize;
+    let (mut arrays, mut extra_arrays, mut storage) = (Vec::new(), Vec::new(), Vec::new());
+    let (mut cold, mut extra_cold) = (Vec::new(), Vec::new());
+    let (mut lazy, mut extra_lazy) = (Vec::new(), Vec::new());
+    let (mut dangling, mut extra_dangling) = (Vec::new(), Vec::new());
+    let (mut write, mut read) = (Vec::new(), Vec::new());
+    let (mut row, mut row_mut) = (Vec::new(), Vec::new());
+    let (mut ref_fields, mut mut_fields) = (Vec::new(), Vec::new());
+    let mut needs = Vec::new();
+    let mut array = quote! { 0usize };
+    for (field, options) in Iterator::zip(data.fields.iter(), options.iter()) {
+        let (vis, ident, ty) = (&field.vis, &field.ident, &field.ty);
+        let index = array.clone();
+        array = quote! { #array + 1 };
+        let (is_cold, is_lazy) = (options.cold, options.lazy);
+
+        if options.flatten {
+            let columns = quote! { <#ty as ::soak::Columns> };
+            let nested = quote! { #columns::ARRAYS.len() };
+            array = quote! { #index + #nested };
+            let fields = quote! { <#ty as ::dioptre::Fields>::SIZES.len() };
+            arrays.push(quote! { #columns::ARRAYS });
+            cold.push(match options.cold {
+                true => quote! { &[true; #nested] },
+                false => quote! { #columns::COLD },
+            });
+            lazy.push(quote! { #columns::LAZY });
+            storage.push(quote! { &[::soak::Storage::Flatten { start: #index, len: #nested }] });
+            storage.push(quote! {
+                &::soak::__private::nest::<{ #fields }>(#columns::STORAGE, #index)
+            });
+            dangling.push(quote! {
+                ::core::borrow::Borrow::<[::core::ptr::NonNull<u8>]>::borrow(&#columns::dangling())
+            });
+
+            let pointers = quote! { &pointers[#index..#index + #nested] };
+            needs.push(quote! {
+                if (#index..#index + #nested).contains(&array) {
+                    return #columns::needs(&self.#ident, array - (#index));
+                }
+            });
+            write.push(quote! {
+                #columns::write(::core::ptr::read(&row.#ident), #pointers, index);
+            });
+            read.push(quote! { #ident: #columns::read(#pointers, index) });
+            row.push(quote! { #ident: #columns::row(#pointers, index) });
+            row_mut.push(quote! { #ident: #columns::row_mut(#pointers, index) });
+            ref_fields.push(quote! { #vis #ident: #columns::Ref<#lifetime> });
+            mut_fields.push(quote! { #vis #ident: #columns::Mut<#lifetime> });
+            continue;
+        }
+
+        match options.nullable {
+            None if options.bits || is_bool(ty) => {
+                arrays.push(quote! { &[::soak::Array::Bits] });
+                cold.push(quote! { &[#is_cold] });
+                lazy.push(quote! { &[false] });
+                storage.push(quote! { &[::soak::Storage::Bits { array: #index }] });
+                dangling.push(quote! { &[::core::ptr::NonNull::<usize>::dangling().cast()] });
+
+                let bits = quote! { pointers[#index].as_ptr() };
+                write.push(quote! {
+                    ::soak::__private::set(#bits, index, ::core::ptr::read(&row.#ident));
+                });
+                read.push(quote! { #ident: ::soak::__private::get(#bits, index) });
+                row.push(quote! { #ident: ::soak::__private::get(#bits, index) });
+                row_mut.push(quote! { #ident: ::soak::BitMut::from_raw_parts(#bits, index) });
+                ref_fields.push(quote! { #vis #ident: bool });
+                mut_fields.push(quote! { #vis #ident: ::soak::BitMut<#lifetime> });
+            }
+            None => {
+                arrays.push(quote! { &[::soak::Array::of::<#ty>()] });
+                cold.push(quote! { &[#is_cold] });
+                lazy.push(quote! { &[#is_lazy] });
+                storage.push(quote! { &[::soak::Storage::Plain { array: #index }] });
+                dangling.push(quote! { &[::core::ptr::NonNull::<#ty>::dangling().cast()] });
+
+                let values = quote! { (pointers[#index].as_ptr() as *mut #ty) };
+                write.push(quote! {
+                    ::core::ptr::write(#values.add(index), ::core::ptr::read(&row.#ident));
+                });
+                read.push(quote! { #ident: ::core::ptr::read(#values.add(index)) });
+                row.push(quote! { #ident: &*#values.add(index) });
+                row_mut.push(quote! { #ident: &mut *#values.add(index) });
+                ref_fields.push(quote! { #vis #ident: &#lifetime #ty });
+                mut_fields.push(quote! { #vis #ident: &#lifetime mut #ty });
+            }
+            Some(inner) => {
+                let validity = quote! { #len + #bitsets };
+                bitsets += 1;
+                arrays.push(quote! { &[::soak::Array::of::<#inner>()] });
+                extra_arrays.push(quote! { &[::soak::Array::Bits] });
+                cold.push(quote! { &[#is_cold] });
+                extra_cold.push(quote! { &[#is_cold] });
+                lazy.push(quote! { &[#is_lazy] });
+                extra_lazy.push(quote! { &[#is_lazy] });
+                storage.push(quote! {
+                    &[::soak::Storage::Nullable { values: #index, validity: #validity }]
+                });
+                dangling.push(quote! { &[::core::ptr::NonNull::<#inner>::dangling().cast()] });
+                extra_dangling.push(quote! { &[::core::ptr::NonNull::<usize>::dangling().cast()] });
+
+                // A lazy field's bitset is null until it is allocated, when every row is empty.
+                // Mutable row views are only handed out once it has been.
+                let values = quote! { (pointers[#index].as_ptr() as *mut #inner) };
+                let validity_mut = quote! { pointers[#validity].as_ptr() };
+                let (bits, is_some, clear) = match is_lazy {
+                    true => {
+                        needs.push(quote! {
+                            if array == #index || array == #validity {
+                                return self.#ident.is_some();
+                            }
+                        });
+                        let bits = quote! { ::soak::__private::bitset(pointers[#validity]) };
+                        let is_some = quote! { ::soak::__private::get_lazy(#bits, index) };
+                        let clear = quote! { ::soak::__private::clear_lazy(#bits, index) };
+                        (bits, is_some, clear)
+                    }
+                    false => {
+                        let bits = quote! { pointers[#validity].as_ptr() };
+                        let is_some = quote! { ::soak::__private::get(#bits, index) };
+                        let clear = quote! { ::soak::__private::set(#bits, index, false) };
+                        (bits, is_some, clear)
+                    }
+                };
+                write.push(quote! {
+                    match ::core::ptr::read(&row.#ident) {
+                        ::core::option::Option::Some(value) => {
+                            ::core::ptr::write(#values.add(index), value);
+                            ::soak::__private::set(#bits, index, true);
+                        }
+                        ::core::option::Option::None => #clear,
+                    }
+                });
+                read.push(quote! {
+                    #ident: if #is_some {
+                        ::core::option::Option::Some(::core::ptr::read(#values.add(index)))
+                    } else {
+                        ::core::option::Option::None
+                    }
+                });
+                row.push(quote! {
+                    #ident: if #is_some {
+                        ::core::option::Option::Some(&*#values.add(index))
+                    } else {
+                        ::core::option::Option::None
+                    }
+                });
+                row_mut.push(quote! {
+                    #ident: ::soak::NullableMut::from_raw_parts(#values, #validity_mut, index)
+                });
+                ref_fields.push(quote! {
+                    #vis #ident: ::core::option::Option<&#lifetime #inner>
+                });
+                mut_fields.push(quote! { #vis #ident: ::soak::NullableMut<#lifetime, #inner> });
+            }
+        }
+    }
+    let pointers = quote! { #len + #bitsets };
+    let entries: Vec<_> = data.fields.iter().zip(options.iter()).map(|(field, options)| {
+        let ty = &field.ty;
+        match options.flatten {
+            true => quote! { 1 + <#ty as ::dioptre::Fields>::SIZES.len() },
+            false => quote! { 1 },
+        }
+    }).collect();
+    let entries = quote! { 0usize #(+ #entries)* };
+    arrays.extend(extra_arrays);
+    cold.extend(extra_cold);
+    lazy.extend(extra_lazy);
+    dangling.extend(extra_dangling);
+
+    // Row views borrow each field separately, so they need their own lifetime parameter.
+    let vis = &ast.vis;
+    let needs = match needs.is_empty() {
+        true => None,
+        false => Some(quote! {
+            fn needs(&self, array: usize) -> bool {
+                #(#needs)*
+                true
+            }
+        }),
+    };
+
+    let ref_ident = format_ident!("{}Ref", ident);
+    let mut_ident = format_ident!("{}Mut", ident);
+    let ref_doc = format!("Shared references to the fields of one row of [`{}`]s.", ident);
+    let mut_doc = format!("Mutable references to the fields of one row of [`{}`]s.", ident);
+    let mut view_generics = ast.generics.clone();
+    view_generics.params.insert(0, GenericParam::Lifetime(LifetimeDef::new(lifetime.clone())));
+    let (view_impl_generics, view_ty_generics, _) = view_generics.split_for_impl();
 
     let expanded = quote! {
+        #[doc = #ref_doc]
+        #[allow(dead_code)]
+        #vis struct #ref_ident #view_impl_generics #where_clause { #(#ref_fields,)* }
+
+        impl #view_impl_generics ::core::clone::Clone for #ref_ident #view_ty_generics
+        #where_clause {
+            fn clone(&self) -> Self { *self }
+        }
+
+        impl #view_impl_generics ::core::marker::Copy for #ref_ident #view_ty_generics
+        #where_clause {}
+
+        #[doc = #mut_doc]
+        #[allow(dead_code)]
+        #vis struct #mut_ident #view_impl_generics #where_clause { #(#mut_fields,)* }
+
         unsafe impl #impl_generics ::soak::Columns for #ident #ty_generics #where_clause {
             type Pointers = [::core::ptr::NonNull<u8>; #pointers];
 
+            const ARRAYS: &'static [::soak::Array] =
+                &::soak::__private::concat::<::soak::Array, { #pointers }>(&[#(#arrays,)*]);
+            const COLD: &'static [bool] =
+                &::soak::__private::concat::<bool, { #pointers }>(&[#(#cold,)*]);
+            const LAZY: &'static [bool] =
+                &::soak::__private::concat::<bool, { #pointers }>(&[#(#lazy,)*]);
+            const STORAGE: &'static [::soak::Storage] =
+                &::soak::__private::concat::<::soak::Storage, { #entries }>(&[#(#storage,)*]);
+
+            #align
+
+            type Ref<#lifetime> = #ref_ident #view_ty_generics where Self: #lifetime;
+            type Mut<#lifetime> = #mut_ident #view_ty_generics where Self: #lifetime;
+
             fn dangling() -> Self::Pointers {
-                [#(::core::ptr::NonNull::<#dangling>::dangling().cast(),)*]
+                ::soak::__private::concat::<::core::ptr::NonNull<u8>, { #pointers }>(
+                    &[#(#dangling,)*]
+                )
+            }
+
+            #needs
+
+            unsafe fn write(self, pointers: &[::core::ptr::NonNull<u8>], index: usize) {
+                let row = ::core::mem::ManuallyDrop::new(self);
+                #(#write)*
+            }
+
+            unsafe fn read(pointers: &[::core::ptr::NonNull<u8>], index: usize) -> Self {
+                #ident { #(#read,)* }
+            }
+
+            unsafe fn row<#lifetime>(pointers: &[::core::ptr::NonNull<u8>], index: usize)
+                -> Self::Ref<#lifetime> where Self: #lifetime
+            {
+                #ref_ident { #(#row,)* }
+            }
+
+            unsafe fn row_mut<#lifetime>(pointers: &[::core::ptr::NonNull<u8>], index: usize)
+                -> Self::Mut<#lifetime> where Self: #lifetime
+            {
+                #mut_ident { #(#row_mut,)* }
             }
         }
     };
 
     proc_macro::TokenStream::from(expanded)
 }
+
+/// Options from `#[soak(...)]` attributes on the struct itself.
+#[derive(Default)]
+struct ContainerOptions {
+    align: Option<usize>,
+}
+
+impl ContainerOptions {
+    fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut options = ContainerOptions::default();
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("soak")) {
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => return Err(Error::new_spanned(meta, "expected `#[soak(...)]`")),
+            };
+
+            for nested in list.nested.iter() {
+                match *nested {
+                    NestedMeta::Meta(Meta::NameValue(ref meta)) if meta.path.is_ident("align") => {
+                        let align = match meta.lit {
+                            Lit::Int(ref lit) => lit.base10_parse::<usize>()?,
+                            ref lit => return Err(Error::new_spanned(lit, "expected an integer")),
+                        };
+                        if !align.is_power_of_two() {
+                            let message = "alignment must be a power of two";
+                            return Err(Error::new_spanned(&meta.lit, message));
+                        }
+                        options.align = Some(align);
+                    }
+                    _ => return Err(Error::new_spanned(nested, "unknown `soak` option")),
+                }
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Options from `#[soak(...)]` attributes on a field.
+#[derive(Default)]
+struct FieldOptions<'a> {
+    /// The `T` in a `#[soak(nullable)]` field of type `Option<T>`.
+    nullable: Option<&'a Type>,
+    /// Whether the field is marked `#[soak(bits)]`.
+    bits: bool,
+    /// Whether the field is marked `#[dioptre(flatten)]`, which `Fields` also reads.
+    flatten: bool,
+    /// Whether the field is marked `#[soak(cold)]`.
+    cold: bool,
+    /// Whether the field is marked `#[soak(lazy)]`.
+    lazy: bool,
+}
+
+impl<'a> FieldOptions<'a> {
+    fn parse(field: &'a Field) -> Result<Self, Error> {
+        let flatten = is_flattened(&field.attrs);
+        let mut options = FieldOptions { flatten, ..FieldOptions::default() };
+        for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("soak")) {
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => return Err(Error::new_spanned(meta, "expected `#[soak(...)]`")),
+            };
+
+            for nested in list.nested.iter() {
+                match *nested {
+                    NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("nullable") => {
+                        let message = "`nullable` fields must have type `Option<T>`";
+                        let inner = option_inner(&field.ty);
+                        let inner = inner.ok_or_else(|| Error::new_spanned(&field.ty, message))?;
+                        options.nullable = Some(inner);
+                    }
+                    NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("bits") => {
+                        options.bits = true;
+                    }
+                    NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("flatten") => {
+                        let message = "use `#[dioptre(flatten)]` to flatten a field";
+                        return Err(Error::new_spanned(nested, message));
+                    }
+                    NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("cold") => {
+                        options.cold = true;
+                    }
+                    NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("lazy") => {
+                        options.lazy = true;
+                    }
+                    _ => return Err(Error::new_spanned(nested, "unknown `soak` option")),
+                }
+            }
+        }
+
+        if options.lazy && (options.bits || options.cold) {
+            let message = "`lazy` cannot be combined with `bits` or `cold`";
+            return Err(Error::new_spanned(&field.ty, message));
+        }
+        if options.lazy && options.nullable.is_none() && is_bool(&field.ty) {
+            let message = "`lazy` fields cannot have type `bool`";
+            return Err(Error::new_spanned(&field.ty, message));
+        }
+        if options.bits && options.nullable.is_some() {
+            let message = "`bits` and `nullable` cannot be combined";
+            return Err(Error::new_spanned(&field.ty, message));
+        }
+        if options.flatten && (options.bits || options.nullable.is_some() || options.lazy) {
+            let message = "`flatten` cannot be combined with other options";
+            return Err(Error::new_spanned(&field.ty, message));
+        }
+
+        Ok(options)
+    }
+}
+
+/// Check for `#[dioptre(flatten)]` among a field's attributes. Any other `dioptre` options are
+/// left for `#[derive(Fields)]` to check.
+fn is_flattened(attrs: &[Attribute]) -> bool {
+    let attrs = attrs.iter().filter(|attr| attr.path.is_ident("dioptre"));
+    attrs.filter_map(|attr| attr.parse_meta().ok()).any(|meta| match meta {
+        Meta::List(list) => list.nested.iter().any(|nested| match *nested {
+            NestedMeta::Meta(Meta::Path(ref path)) => path.is_ident("flatten"),
+            _ => false,
+        }),
+        _ => false,
+    })
+}
+
+/// Check whether a type is written as `bool`.
+fn is_bool(ty: &Type) -> bool {
+    match *ty {
+        Type::Path(ref path) => path.qself.is_none() && path.path.is_ident("bool"),
+        _ => false,
+    }
+}
+
+/// Get the `T` in a type written as `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = match *ty {
+        Type::Path(ref path) if path.qself.is_none() => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    match segment.arguments {
+        PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => match args.args[0] {
+            GenericArgument::Type(ref inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
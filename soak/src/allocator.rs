@@ -0,0 +1,83 @@
+use core::{cmp, ptr};
+use alloc::alloc::{alloc, dealloc, realloc, Layout};
+
+/// A memory allocator that can back a [`RawTable`](crate::RawTable).
+///
+/// This mirrors [`GlobalAlloc`](core::alloc::GlobalAlloc), but can be implemented by allocators
+/// with a limited lifetime, like an arena borrowed for a single frame.
+///
+/// # Safety
+///
+/// * Memory returned by `alloc` or `realloc` must remain valid until it is passed to `dealloc` or
+///   `realloc`, or until the allocator itself is dropped.
+/// * `alloc` must return memory that fits `layout`, or null to indicate failure.
+/// * `realloc` must return memory that fits `layout` resized to `new_size`, holding the old
+///   contents, or null to indicate failure, in which case the old memory is left untouched.
+pub unsafe trait Allocator {
+    /// Allocate memory as described by `layout`.
+    ///
+    /// # Safety
+    ///
+    /// `layout` must have a non-zero size.
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8;
+
+    /// Deallocate memory previously returned by `alloc`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `self.alloc(layout)`, or by `self.realloc` with a new size
+    /// of `layout.size()`, and not already deallocated.
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout);
+
+    /// Resize memory previously returned by `alloc` to `new_size` bytes, preserving its contents up
+    /// to the smaller of the two sizes.
+    ///
+    /// By default, this allocates new memory, copies the contents and deallocates the old memory.
+    /// Allocators that can often resize memory in place should override it.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `self.alloc(layout)`, or by `self.realloc` with a new size
+    /// of `layout.size()`, and not already deallocated. `new_size` must be non-zero, and must not
+    /// overflow `isize` when rounded up to a multiple of `layout.align()`.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let data = self.alloc(Layout::from_size_align_unchecked(new_size, layout.align()));
+        if !data.is_null() {
+            ptr::copy_nonoverlapping(ptr, data, cmp::min(layout.size(), new_size));
+            self.dealloc(ptr, layout);
+        }
+        data
+    }
+}
+
+/// The global memory allocator, registered with `#[global_allocator]`.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Global;
+
+unsafe impl Allocator for Global {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 { alloc(layout) }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) { dealloc(ptr, layout) }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        realloc(ptr, layout, new_size)
+    }
+}
+
+unsafe impl<A: Allocator + ?Sized> Allocator for &A {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 { A::alloc(self, layout) }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) { A::dealloc(self, ptr, layout) }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        A::realloc(self, ptr, layout, new_size)
+    }
+}
+
+/// Bump-allocate from a [`quickdry::Arena`]. Memory is only freed when the arena is dropped.
+#[cfg(feature = "quickdry")]
+unsafe impl Allocator for quickdry::Arena {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 { quickdry::Arena::alloc(self, layout) }
+
+    unsafe fn dealloc(&self, _: *mut u8, _: Layout) {}
+}
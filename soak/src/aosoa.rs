@@ -0,0 +1,372 @@
+use core::{cmp, mem, ptr, slice};
+use core::borrow::{Borrow, BorrowMut};
+use core::marker::PhantomData;
+use alloc::alloc::Layout;
+use dioptre::Field;
+
+use crate::{plain_array, handle_reserve, Allocator, Array, Columns, Global, TryReserveError};
+
+/// A growable array of tiles, each holding `N` rows as parallel arrays of `T`'s fields.
+///
+/// Where a [`Table`](crate::Table) places each field array in its own region of the allocation,
+/// `AoSoATable` interleaves them in fixed-size tiles:
+///
+/// ```text
+/// [field 1 x N, field 2 x N][field 1 x N, field 2 x N]...
+/// ```
+///
+/// This keeps each row's fields within a single tile, while still handing out each field's lanes
+/// as contiguous `[F; N]` arrays. Each array is aligned to at least [`Columns::ALIGN`]. Tiles are
+/// kept in a single allocation, so `#[soak(cold)]` and `#[soak(lazy)]` fields are stored alongside
+/// the others.
+pub struct AoSoATable<T: Columns, const N: usize, A: Allocator = Global> {
+    data: ptr::NonNull<u8>,
+    tiles: usize,
+    len: usize,
+    alloc: A,
+    _marker: PhantomData<T>,
+}
+
+unsafe impl<T: Columns + Send, const N: usize, A: Allocator + Send> Send for AoSoATable<T, N, A> {}
+unsafe impl<T: Columns + Sync, const N: usize, A: Allocator + Sync> Sync for AoSoATable<T, N, A> {}
+
+impl<T: Columns, const N: usize, A: Allocator + Default> Default for AoSoATable<T, N, A> {
+    /// Create an empty `AoSoATable` without allocating.
+    fn default() -> Self { Self::new_in(A::default()) }
+}
+
+impl<T: Columns, const N: usize> AoSoATable<T, N> {
+    /// Create an empty `AoSoATable` without allocating.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero.
+    pub fn new() -> Self { Self::default() }
+
+    /// Create an empty `AoSoATable` with enough space for `capacity` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero, or if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn with_capacity(capacity: usize) -> Self { Self::with_capacity_in(capacity, Global) }
+}
+
+impl<T: Columns, const N: usize, A: Allocator> AoSoATable<T, N, A> {
+    /// Create an empty `AoSoATable` in `alloc` without allocating.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero.
+    pub fn new_in(alloc: A) -> Self {
+        assert!(N > 0, "tiles must contain at least one row");
+        let data = unsafe { ptr::NonNull::new_unchecked(Self::align() as *mut u8) };
+        let tiles = if Self::block() == Some(0) { usize::MAX / N } else { 0 };
+        AoSoATable { data, tiles, len: 0, alloc, _marker: PhantomData }
+    }
+
+    /// Create an empty `AoSoATable` in `alloc` with enough space for `capacity` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero, or if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        let mut table = Self::new_in(alloc);
+        table.reserve_exact(capacity);
+        table
+    }
+
+    /// Get the number of initialized rows.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Check whether the table contains no rows.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Get the number of rows the table can hold without reallocating.
+    pub fn capacity(&self) -> usize { self.tiles * N }
+
+    /// Get the number of tiles containing initialized rows.
+    pub fn tiles(&self) -> usize { self.len.div_ceil(N) }
+
+    /// Ensure that the table contains enough space for `extra` more rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn reserve_exact(&mut self, extra: usize) { handle_reserve(self.try_reserve_exact(extra)) }
+
+    /// Ensure that the table contains enough space for `extra` more rows, returning an error if
+    /// the allocation fails.
+    pub fn try_reserve_exact(&mut self, extra: usize) -> Result<(), TryReserveError> {
+        if self.capacity() - self.len >= extra {
+            return Ok(());
+        }
+
+        let rows = usize::checked_add(self.len, extra).ok_or(TryReserveError::CapacityOverflow)?;
+        self.relayout(rows.div_ceil(N))
+    }
+
+    /// Ensure that the table contains enough space for `extra` more rows, growing it
+    /// geometrically to amortize the cost of repeated calls.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn reserve(&mut self, extra: usize) { handle_reserve(self.try_reserve(extra)) }
+
+    /// Ensure that the table contains enough space for `extra` more rows, growing it
+    /// geometrically and returning an error if the allocation fails.
+    pub fn try_reserve(&mut self, extra: usize) -> Result<(), TryReserveError> {
+        if self.capacity() - self.len >= extra {
+            return Ok(());
+        }
+
+        let rows = usize::checked_add(self.len, extra).ok_or(TryReserveError::CapacityOverflow)?;
+        let tiles = cmp::max(self.tiles.saturating_mul(2), rows.div_ceil(N));
+        self.relayout(tiles)
+    }
+
+    /// Append a row to the end of the table.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn push(&mut self, row: T) {
+        self.reserve(1);
+        let (pointers, lane) = self.row(self.len);
+        unsafe { row.write(pointers.borrow(), lane); }
+        self.len += 1;
+    }
+
+    /// Remove the last row from the table and return it, or `None` if it is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.len -= 1;
+        let (pointers, lane) = self.row(self.len);
+        unsafe { Some(T::read(pointers.borrow(), lane)) }
+    }
+
+    /// Remove all rows from the table, without affecting its capacity.
+    pub fn clear(&mut self) { self.truncate(0) }
+
+    /// Shorten the table to `len` rows, dropping the rest.
+    ///
+    /// This has no effect if `len` is greater than the table's current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+
+        let end = mem::replace(&mut self.len, len);
+        if mem::needs_drop::<T>() {
+            for index in len..end {
+                let (pointers, lane) = self.row(index);
+                unsafe { drop(T::read(pointers.borrow(), lane)); }
+            }
+        }
+    }
+
+    /// Borrow the fields of the row at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<T::Ref<'_>> {
+        if index >= self.len {
+            return None;
+        }
+
+        let (pointers, lane) = self.row(index);
+        unsafe { Some(T::row(pointers.borrow(), lane)) }
+    }
+
+    /// Mutably borrow the fields of the row at `index`, or `None` if it is out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<T::Mut<'_>> {
+        if index >= self.len {
+            return None;
+        }
+
+        let (pointers, lane) = self.row(index);
+        unsafe { Some(T::row_mut(pointers.borrow(), lane)) }
+    }
+
+    /// Get the initialized lanes of a field in tile `tile`.
+    ///
+    /// This contains `N` elements, except in a partially filled last tile.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tile` is out of bounds, or if the field is not stored as a plain array of its own
+    /// type.
+    pub fn lanes<F>(&self, field: Field<T, F>, tile: usize) -> &[F] {
+        assert!(tile < self.tiles(), "tile out of bounds");
+        let len = cmp::min(self.len - tile * N, N);
+        unsafe { slice::from_raw_parts(self.lane(field.index(), tile) as *const F, len) }
+    }
+
+    /// Get the initialized lanes of a field in tile `tile`, mutably.
+    ///
+    /// This contains `N` elements, except in a partially filled last tile.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tile` is out of bounds, or if the field is not stored as a plain array of its own
+    /// type.
+    pub fn lanes_mut<F>(&mut self, field: Field<T, F>, tile: usize) -> &mut [F] {
+        assert!(tile < self.tiles(), "tile out of bounds");
+        let len = cmp::min(self.len - tile * N, N);
+        unsafe { slice::from_raw_parts_mut(self.lane(field.index(), tile) as *mut F, len) }
+    }
+
+    /// Iterate over a field's lanes in each full tile.
+    ///
+    /// Rows in a partially filled last tile are skipped, and can be accessed via
+    /// [`lanes`](AoSoATable::lanes).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn chunks<'a, F: 'a>(&'a self, field: Field<T, F>)
+        -> impl DoubleEndedIterator<Item = &'a [F; N]> + ExactSizeIterator
+    {
+        let block = Self::block().expect("tile size overflow");
+        let lanes = self.lane(field.index(), 0);
+        (0..self.len / N).map(move |tile| unsafe { &*(lanes.add(tile * block) as *const [F; N]) })
+    }
+
+    /// Iterate mutably over a field's lanes in each full tile.
+    ///
+    /// Rows in a partially filled last tile are skipped, and can be accessed via
+    /// [`lanes_mut`](AoSoATable::lanes_mut).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn chunks_mut<'a, F: 'a>(&'a mut self, field: Field<T, F>)
+        -> impl DoubleEndedIterator<Item = &'a mut [F; N]> + ExactSizeIterator
+    {
+        let block = Self::block().expect("tile size overflow");
+        let lanes = self.lane(field.index(), 0);
+        (0..self.len / N).map(move |tile| unsafe { &mut *(lanes.add(tile * block) as *mut [F; N]) })
+    }
+
+    /// Compute the alignment of a tile.
+    fn align() -> usize {
+        T::ARRAYS.iter().fold(T::ALIGN, |align, array| cmp::max(align, array.align()))
+    }
+
+    /// Compute the size of a tile, or `None` on overflow.
+    fn block() -> Option<usize> {
+        let mask = Self::align() - 1;
+        let size = match Self::offsets().last() {
+            Some(array) => array.and_then(|(offset, len)| usize::checked_add(offset, len))?,
+            None => 0,
+        };
+        Some(usize::checked_add(size, mask)? & !mask)
+    }
+
+    /// Compute the offset of each array's lanes within a tile, along with their size, or `None`
+    /// from the first one that overflows.
+    fn offsets() -> impl Iterator<Item = Option<(usize, usize)>> {
+        let mut end = Some(0);
+        T::ARRAYS.iter().map(move |array| {
+            let mask = cmp::max(array.align(), T::ALIGN) - 1;
+            let offset = usize::checked_add(end?, mask)? & !mask;
+            let len = array.len(N)?;
+            end = usize::checked_add(offset, len);
+            Some((offset, len))
+        })
+    }
+
+    /// Get a pointer to the first lane of field `index` in tile `tile`.
+    fn lane(&self, index: usize, tile: usize) -> *mut u8 {
+        let block = Self::block().expect("tile size overflow");
+        let (offset, _) = Self::offsets().nth(plain_array::<T>(index)).unwrap().unwrap();
+        unsafe { self.data.as_ptr().add(tile * block + offset) }
+    }
+
+    /// Get pointers to each array of the tile containing the row at `index`, along with the row's
+    /// lane within that tile.
+    fn row(&self, index: usize) -> (T::Pointers, usize) {
+        let block = Self::block().expect("tile size overflow");
+        let tile = unsafe { self.data.as_ptr().add(index / N * block) };
+
+        let mut pointers = T::dangling();
+        let dst = pointers.borrow_mut().iter_mut();
+        for (pointer, array) in Iterator::zip(dst, Self::offsets()) {
+            let (offset, len) = array.unwrap();
+            if len > 0 {
+                *pointer = unsafe { ptr::NonNull::new_unchecked(tile.add(offset)) };
+            }
+        }
+        (pointers, index % N)
+    }
+
+    /// Compute the layout of an allocation for `tiles` tiles, or `None` on overflow.
+    fn layout(tiles: usize) -> Option<Layout> {
+        let size = usize::checked_mul(tiles, Self::block()?)?;
+        Layout::from_size_align(size, Self::align()).ok()
+    }
+
+    /// Move the initialized tiles into a new allocation of `tiles` tiles.
+    fn relayout(&mut self, tiles: usize) -> Result<(), TryReserveError> {
+        unsafe {
+            // Tables of zero-sized rows start out with the maximum capacity, so this only fails.
+            let layout = Self::layout(tiles).ok_or(TryReserveError::CapacityOverflow)?;
+            if layout.size() == 0 || usize::checked_mul(tiles, N).is_none() {
+                return Err(TryReserveError::CapacityOverflow);
+            }
+
+            let data = self.alloc.alloc(layout);
+            let data = ptr::NonNull::new(data).ok_or(TryReserveError::AllocError { layout })?;
+
+            // Bitsets are accessed a word at a time, so must start out initialized.
+            if T::ARRAYS.contains(&Array::Bits) {
+                ptr::write_bytes(data.as_ptr(), 0, layout.size());
+            }
+
+            let block = Self::block().unwrap_unchecked();
+            ptr::copy_nonoverlapping(self.data.as_ptr(), data.as_ptr(), self.tiles() * block);
+
+            self.deallocate();
+            self.data = data;
+            self.tiles = tiles;
+            Ok(())
+        }
+    }
+
+    /// Free the tiles without dropping their elements.
+    unsafe fn deallocate(&mut self) {
+        // Tables whose tiles overflow never allocate.
+        match Self::layout(self.tiles) {
+            Some(layout) if layout.size() > 0 => self.alloc.dealloc(self.data.as_ptr(), layout),
+            _ => {}
+        }
+    }
+}
+
+impl<T: Columns, const N: usize, A: Allocator> Drop for AoSoATable<T, N, A> {
+    /// Drop the initialized rows and free the tiles.
+    fn drop(&mut self) {
+        self.truncate(0);
+        unsafe { self.deallocate() }
+    }
+}
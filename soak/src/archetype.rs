@@ -0,0 +1,366 @@
+//! Archetype storage for entity-component systems.
+//!
+//! A [`World`] groups entities by the exact set of component types they have. Each group, or
+//! [`Archetype`], stores its components in a [`DynTable`] with one column per type, so systems can
+//! iterate over a component as a slice. Adding or removing a component moves the entity's row to
+//! the archetype for its new set of types.
+//!
+//! Components must be `Send` and `Sync`, so that a `World` can be shared between threads.
+//!
+//! ```
+//! use soak::archetype::World;
+//!
+//! let mut world = World::new();
+//! let entity = world.spawn();
+//! world.insert(entity, 1.5f32);
+//! world.insert(entity, "player");
+//!
+//! for speeds in world.columns_mut::<f32>() {
+//!     speeds.iter_mut().for_each(|speed| *speed *= 2.0);
+//! }
+//! assert_eq!(world.get::<f32>(entity), Some(&3.0));
+//! assert_eq!(world.remove::<&str>(entity), Some("player"));
+//! ```
+
+use core::{mem, ptr};
+use core::any::TypeId;
+use core::convert::TryFrom;
+use alloc::{boxed::Box, collections::BTreeMap, vec, vec::Vec};
+
+use crate::{Column, DynTable};
+
+/// A handle to an entity in a [`World`].
+///
+/// Once an entity is despawned, its handle stays invalid even if its slot is recycled for a new
+/// entity, as each reuse of a slot bumps its generation.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    /// Get the index of the entity's slot.
+    pub fn index(&self) -> u32 { self.index }
+
+    /// Get the number of times the entity's slot had been reused when it was spawned.
+    pub fn generation(&self) -> u32 { self.generation }
+}
+
+/// The position of an entity's components within a [`World`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Location {
+    /// The index of the entity's archetype in [`World::archetypes`].
+    pub archetype: usize,
+    /// The index of the entity's row within its archetype.
+    pub row: usize,
+}
+
+/// A table of entities that share the same set of component types.
+pub struct Archetype {
+    types: Box<[TypeId]>,
+    table: DynTable,
+    entities: Vec<Entity>,
+}
+
+impl Archetype {
+    /// Create an empty archetype with `columns`, which must be sorted by type.
+    fn new(columns: Vec<Column>) -> Self {
+        let types = columns.iter().map(|column| column.type_id().unwrap()).collect();
+        Archetype { types, table: DynTable::new(columns), entities: Vec::new() }
+    }
+
+    /// Get the archetype's component types, in sorted order.
+    pub fn types(&self) -> &[TypeId] { &self.types }
+
+    /// Get the number of entities in the archetype.
+    pub fn len(&self) -> usize { self.entities.len() }
+
+    /// Check whether the archetype contains no entities.
+    pub fn is_empty(&self) -> bool { self.entities.is_empty() }
+
+    /// Get the archetype's entities, in row order.
+    pub fn entities(&self) -> &[Entity] { &self.entities }
+
+    /// Check whether the archetype's entities have an `F` component.
+    pub fn has<F: 'static>(&self) -> bool { self.column_index(TypeId::of::<F>()).is_some() }
+
+    /// Get the `F` component of each entity, or `None` if they do not have one.
+    pub fn column<F: 'static>(&self) -> Option<&[F]> {
+        let index = self.column_index(TypeId::of::<F>())?;
+        unsafe { self.table.column(index, self.entities.len()) }
+    }
+
+    /// Get the `F` component of each entity mutably, or `None` if they do not have one.
+    fn column_mut<F: 'static>(&mut self) -> Option<&mut [F]> {
+        let index = self.column_index(TypeId::of::<F>())?;
+        unsafe { self.table.column_mut(index, self.entities.len()) }
+    }
+
+    /// Find the column holding components of type `type_id`.
+    fn column_index(&self, type_id: TypeId) -> Option<usize> {
+        self.types.binary_search(&type_id).ok()
+    }
+
+    /// Ensure there is space for one more row.
+    fn reserve(&mut self) { self.table.reserve(self.entities.len(), 1) }
+
+    /// Append `entity` with an uninitialized row, returning the row's index.
+    fn push(&mut self, entity: Entity) -> usize {
+        self.reserve();
+        self.entities.push(entity);
+        self.entities.len() - 1
+    }
+
+    /// Move the last row into `row` without dropping the old contents of `row`, returning the
+    /// entity that was moved, if any.
+    ///
+    /// # Safety
+    ///
+    /// The components in `row` must already have been moved out or dropped.
+    unsafe fn swap_forget(&mut self, row: usize) -> Option<Entity> {
+        let last = self.entities.len() - 1;
+        self.entities.swap_remove(row);
+        if row == last {
+            return None;
+        }
+
+        self.table.copy(last, row, 1);
+        Some(self.entities[row])
+    }
+
+    /// Swap `row` with the last row and remove it from the archetype, returning the entity that
+    /// was moved into `row`, if any.
+    ///
+    /// The removed components are left initialized just past the archetype's last row, to be
+    /// dropped without the archetype ever tracking them again.
+    unsafe fn swap_remove(&mut self, row: usize) -> Option<Entity> {
+        let last = self.entities.len() - 1;
+        self.entities.swap_remove(row);
+        if row == last {
+            return None;
+        }
+
+        for index in 0..self.types.len() {
+            let size = self.table.columns()[index].size();
+            let data = self.table.ptr(index);
+            ptr::swap_nonoverlapping(data.add(row * size), data.add(last * size), size);
+        }
+        Some(self.entities[row])
+    }
+}
+
+impl Drop for Archetype {
+    /// Drop every entity's components.
+    fn drop(&mut self) {
+        unsafe { self.table.drop_in_place(0, self.entities.len()) }
+    }
+}
+
+/// A slot in a [`World`]'s entity list.
+struct Slot {
+    generation: u32,
+    location: Option<Location>,
+}
+
+/// A collection of entities, stored in archetypes by their set of component types.
+pub struct World {
+    archetypes: Vec<Archetype>,
+    index: BTreeMap<Box<[TypeId]>, usize>,
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+}
+
+impl Default for World {
+    /// Create an empty `World`.
+    fn default() -> Self { Self::new() }
+}
+
+impl World {
+    /// Create an empty `World`.
+    pub fn new() -> Self {
+        let mut index = BTreeMap::new();
+        index.insert(Box::from([]), 0);
+        let archetypes = vec![Archetype::new(Vec::new())];
+        World { archetypes, index, slots: Vec::new(), free: Vec::new() }
+    }
+
+    /// Get the number of live entities.
+    pub fn len(&self) -> usize { self.slots.len() - self.free.len() }
+
+    /// Check whether the world contains no live entities.
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Get the world's archetypes. The first archetype holds entities without components.
+    pub fn archetypes(&self) -> &[Archetype] { &self.archetypes }
+
+    /// Iterate mutably over the `F` components of each archetype that has them.
+    ///
+    /// Archetypes themselves are only available by shared reference, so they stay where each
+    /// entity's [`Location`] expects them.
+    pub fn columns_mut<F: 'static>(&mut self) -> impl Iterator<Item = &mut [F]> {
+        self.archetypes.iter_mut().filter_map(Archetype::column_mut)
+    }
+
+    /// Create an entity without any components.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the world already contains [`u32::MAX`] entities.
+    pub fn spawn(&mut self) -> Entity {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                let index = u32::try_from(self.slots.len()).expect("too many entities");
+                self.slots.push(Slot { generation: 0, location: None });
+                index
+            }
+        };
+
+        let entity = Entity { index, generation: self.slots[index as usize].generation };
+        let row = self.archetypes[0].push(entity);
+        self.slots[index as usize].location = Some(Location { archetype: 0, row });
+        entity
+    }
+
+    /// Destroy `entity`, dropping its components. Returns `false` if it was already despawned.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        let location = match self.location(entity) {
+            Some(location) => location,
+            None => return false,
+        };
+
+        let slot = &mut self.slots[entity.index as usize];
+        slot.location = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(entity.index);
+
+        // Remove the row before dropping it, so a panicking destructor leaks the rest of its
+        // components rather than leaving them for the archetype to drop again.
+        let archetype = &mut self.archetypes[location.archetype];
+        unsafe {
+            if let Some(moved) = archetype.swap_remove(location.row) {
+                self.slots[moved.index as usize].location = Some(location);
+            }
+            archetype.table.drop_in_place(archetype.entities.len(), 1);
+        }
+        true
+    }
+
+    /// Check whether `entity` has been spawned and not yet despawned.
+    pub fn contains(&self, entity: Entity) -> bool { self.location(entity).is_some() }
+
+    /// Get the position of `entity`'s components, or `None` if it has been despawned.
+    pub fn location(&self, entity: Entity) -> Option<Location> {
+        let slot = self.slots.get(entity.index as usize)?;
+        if slot.generation != entity.generation {
+            return None;
+        }
+
+        slot.location
+    }
+
+    /// Borrow `entity`'s `F` component, or `None` if it has none or has been despawned.
+    pub fn get<F: 'static>(&self, entity: Entity) -> Option<&F> {
+        let location = self.location(entity)?;
+        self.archetypes[location.archetype].column::<F>()?.get(location.row)
+    }
+
+    /// Mutably borrow `entity`'s `F` component, or `None` if it has none or has been despawned.
+    pub fn get_mut<F: 'static>(&mut self, entity: Entity) -> Option<&mut F> {
+        let location = self.location(entity)?;
+        self.archetypes[location.archetype].column_mut::<F>()?.get_mut(location.row)
+    }
+
+    /// Give `entity` an `F` component, moving it to a new archetype if it did not have one.
+    /// Returns the previous component, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entity` has been despawned.
+    pub fn insert<F: Send + Sync + 'static>(&mut self, entity: Entity, component: F) -> Option<F> {
+        if let Some(old) = self.get_mut::<F>(entity) {
+            return Some(mem::replace(old, component));
+        }
+
+        let location = self.location(entity).expect("entity has been despawned");
+        let source = &self.archetypes[location.archetype];
+        let type_id = TypeId::of::<F>();
+        let position = source.types.binary_search(&type_id).unwrap_err();
+        let mut columns = source.table.columns().to_vec();
+        columns.insert(position, Column::of::<F>());
+
+        let target = self.archetype(columns);
+        let row = self.relocate(entity, location, target);
+        let archetype = &mut self.archetypes[target];
+        unsafe { archetype.table.ptr(position).cast::<F>().add(row).write(component) }
+        None
+    }
+
+    /// Take `entity`'s `F` component, moving it to a new archetype. Returns `None` if it has none
+    /// or has been despawned.
+    pub fn remove<F: 'static>(&mut self, entity: Entity) -> Option<F> {
+        let location = self.location(entity)?;
+        let source = &self.archetypes[location.archetype];
+        let position = source.column_index(TypeId::of::<F>())?;
+        let mut columns = source.table.columns().to_vec();
+        columns.remove(position);
+
+        let target = self.archetype(columns);
+        self.archetypes[target].reserve();
+        let source = &mut self.archetypes[location.archetype];
+        let component = unsafe { source.table.ptr(position).cast::<F>().add(location.row).read() };
+        self.relocate(entity, location, target);
+        Some(component)
+    }
+
+    /// Find or create the archetype with `columns`, which must be sorted by type.
+    fn archetype(&mut self, columns: Vec<Column>) -> usize {
+        let types: Vec<_> = columns.iter().map(|column| column.type_id().unwrap()).collect();
+        if let Some(&index) = self.index.get(&types[..]) {
+            return index;
+        }
+
+        let index = self.archetypes.len();
+        self.archetypes.push(Archetype::new(columns));
+        self.index.insert(types.into_boxed_slice(), index);
+        index
+    }
+
+    /// Move `entity`'s row from its current archetype to `target`, returning its new row.
+    ///
+    /// Components with no column in `target` are forgotten, so must already have been moved out.
+    fn relocate(&mut self, entity: Entity, location: Location, target: usize) -> usize {
+        let (source, archetype) = pair_mut(&mut self.archetypes, location.archetype, target);
+        let row = archetype.push(entity);
+        unsafe {
+            for index in 0..source.types.len() {
+                if let Some(target_index) = archetype.column_index(source.types[index]) {
+                    let size = source.table.columns()[index].size();
+                    let src = source.table.ptr(index).add(location.row * size);
+                    let dst = archetype.table.ptr(target_index).add(row * size);
+                    ptr::copy_nonoverlapping(src, dst, size);
+                }
+            }
+
+            if let Some(moved) = source.swap_forget(location.row) {
+                self.slots[moved.index as usize].location = Some(location);
+            }
+        }
+
+        self.slots[entity.index as usize].location = Some(Location { archetype: target, row });
+        row
+    }
+}
+
+/// Mutably borrow two distinct elements of `items`.
+fn pair_mut<T>(items: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    assert!(a != b, "elements must be distinct");
+    if a < b {
+        let (head, tail) = items.split_at_mut(b);
+        (&mut head[a], &mut tail[0])
+    } else {
+        let (head, tail) = items.split_at_mut(a);
+        (&mut tail[0], &mut head[b])
+    }
+}
@@ -0,0 +1,253 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of bits in each word of a bitset.
+pub(crate) const WORD_BITS: usize = usize::BITS as usize;
+
+/// Get the number of words needed to hold `bits` bits.
+pub(crate) fn words(bits: usize) -> usize { bits.div_ceil(WORD_BITS) }
+
+/// Get the word holding bit `index` of the bitset at `data`, along with the bit's mask.
+///
+/// Bits are accessed atomically, so rows that share a word can be borrowed by different threads.
+unsafe fn word<'a>(data: *mut u8, index: usize) -> (&'a AtomicUsize, usize) {
+    let word = &*(data as *const AtomicUsize).add(index / WORD_BITS);
+    (word, 1 << (index % WORD_BITS))
+}
+
+/// Read word `index` of the bitset at `data`.
+unsafe fn load(data: *mut u8, index: usize) -> usize {
+    (*(data as *const AtomicUsize).add(index)).load(Ordering::Relaxed)
+}
+
+/// Read bit `index` of the bitset at `data`.
+///
+/// # Safety
+///
+/// `data` must point to an initialized bitset of more than `index` bits.
+pub unsafe fn get(data: *mut u8, index: usize) -> bool {
+    let (word, mask) = word(data, index);
+    word.load(Ordering::Relaxed) & mask != 0
+}
+
+/// Write bit `index` of the bitset at `data`.
+///
+/// # Safety
+///
+/// `data` must point to an initialized bitset of more than `index` bits.
+pub unsafe fn set(data: *mut u8, index: usize, value: bool) {
+    let (word, mask) = word(data, index);
+    if value {
+        word.fetch_or(mask, Ordering::Relaxed);
+    } else {
+        word.fetch_and(!mask, Ordering::Relaxed);
+    }
+}
+
+/// Copy `count` bits of the bitset at `data` from index `src` to index `dst`. The source and
+/// destination may overlap.
+///
+/// # Safety
+///
+/// `data` must point to an initialized bitset of at least `src + count` and `dst + count` bits.
+pub(crate) unsafe fn copy(data: *mut u8, src: usize, dst: usize, count: usize) {
+    if dst <= src {
+        for index in 0..count {
+            set(data, dst + index, get(data, src + index));
+        }
+    } else {
+        for index in (0..count).rev() {
+            set(data, dst + index, get(data, src + index));
+        }
+    }
+}
+
+/// A mutable reference to a bit-packed `bool` field of one row.
+pub struct BitMut<'a> {
+    data: *mut u8,
+    index: usize,
+    _marker: PhantomData<&'a mut bool>,
+}
+
+unsafe impl Send for BitMut<'_> {}
+unsafe impl Sync for BitMut<'_> {}
+
+impl<'a> BitMut<'a> {
+    /// Mutably borrow bit `index` of the bitset at `data`.
+    ///
+    /// # Safety
+    ///
+    /// The bit must not be otherwise accessed for `'a`.
+    pub unsafe fn from_raw_parts(data: *mut u8, index: usize) -> Self {
+        BitMut { data, index, _marker: PhantomData }
+    }
+
+    /// Read the field.
+    pub fn get(&self) -> bool { unsafe { get(self.data, self.index) } }
+
+    /// Write the field.
+    pub fn set(&mut self, value: bool) { unsafe { set(self.data, self.index, value) } }
+
+    /// Write the field, returning its old value.
+    pub fn replace(&mut self, value: bool) -> bool {
+        let old = self.get();
+        self.set(value);
+        old
+    }
+
+    /// Invert the field.
+    pub fn toggle(&mut self) { self.set(!self.get()) }
+}
+
+impl fmt::Debug for BitMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { self.get().fmt(f) }
+}
+
+/// A borrowed range of a bit-packed `bool` field's column.
+#[derive(Copy, Clone)]
+pub struct BitColumn<'a> {
+    data: *mut u8,
+    start: usize,
+    len: usize,
+    _marker: PhantomData<&'a [bool]>,
+}
+
+unsafe impl Send for BitColumn<'_> {}
+unsafe impl Sync for BitColumn<'_> {}
+
+impl<'a> BitColumn<'a> {
+    /// Borrow bits `start..start + len` of the bitset at `data`.
+    ///
+    /// # Safety
+    ///
+    /// The bits must be initialized, and must not be mutated for `'a`.
+    pub unsafe fn from_raw_parts(data: *mut u8, start: usize, len: usize) -> Self {
+        BitColumn { data, start, len, _marker: PhantomData }
+    }
+
+    /// Get the number of rows.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Check whether the column contains no rows.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Read the row at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> bool {
+        assert!(index < self.len, "index out of bounds");
+        unsafe { get(self.data, self.start + index) }
+    }
+
+    /// Iterate over the rows.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = bool> + ExactSizeIterator + 'a {
+        let column = *self;
+        (0..self.len).map(move |index| column.get(index))
+    }
+
+    /// Iterate over the rows a word at a time.
+    ///
+    /// Bit `i` of word `k` holds row `k * usize::BITS + i`. Bits past the end of the last row are
+    /// zero.
+    pub fn words(&self) -> impl DoubleEndedIterator<Item = usize> + ExactSizeIterator + 'a {
+        let BitColumn { data, start, len, .. } = *self;
+        (0..words(len)).map(move |word| unsafe {
+            let (first, shift) = ((start / WORD_BITS) + word, start % WORD_BITS);
+            let rest = len - word * WORD_BITS;
+            let mut bits = load(data, first) >> shift;
+            if shift > 0 && WORD_BITS - shift < rest {
+                bits |= load(data, first + 1) << (WORD_BITS - shift);
+            }
+            if rest < WORD_BITS {
+                bits &= (1 << rest) - 1;
+            }
+            bits
+        })
+    }
+
+    /// Count the rows that are `true`.
+    pub fn count_ones(&self) -> usize {
+        self.words().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Count the rows that are `false`.
+    pub fn count_zeros(&self) -> usize { self.len - self.count_ones() }
+}
+
+impl fmt::Debug for BitColumn<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A mutably borrowed range of a bit-packed `bool` field's column.
+pub struct BitColumnMut<'a> {
+    data: *mut u8,
+    start: usize,
+    len: usize,
+    _marker: PhantomData<&'a mut [bool]>,
+}
+
+unsafe impl Send for BitColumnMut<'_> {}
+unsafe impl Sync for BitColumnMut<'_> {}
+
+impl<'a> BitColumnMut<'a> {
+    /// Mutably borrow bits `start..start + len` of the bitset at `data`.
+    ///
+    /// # Safety
+    ///
+    /// The bits must be initialized, and must not be otherwise accessed for `'a`.
+    pub unsafe fn from_raw_parts(data: *mut u8, start: usize, len: usize) -> Self {
+        BitColumnMut { data, start, len, _marker: PhantomData }
+    }
+
+    /// Get the number of rows.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Check whether the column contains no rows.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Reborrow the rows as a shared [`BitColumn`].
+    pub fn as_column(&self) -> BitColumn<'_> {
+        unsafe { BitColumn::from_raw_parts(self.data, self.start, self.len) }
+    }
+
+    /// Read the row at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> bool { self.as_column().get(index) }
+
+    /// Write the row at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < self.len, "index out of bounds");
+        unsafe { set(self.data, self.start + index, value) }
+    }
+
+    /// Write `value` to every row.
+    pub fn fill(&mut self, value: bool) {
+        for index in self.start..self.start + self.len {
+            unsafe { set(self.data, index, value) }
+        }
+    }
+
+    /// Iterate over the rows mutably.
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = BitMut<'_>> + ExactSizeIterator {
+        let data = self.data;
+        (self.start..self.start + self.len).map(move |index| unsafe {
+            BitMut::from_raw_parts(data, index)
+        })
+    }
+}
+
+impl fmt::Debug for BitColumnMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { self.as_column().fmt(f) }
+}
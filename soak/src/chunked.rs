@@ -0,0 +1,261 @@
+use core::{cmp, mem, slice};
+use core::borrow::Borrow;
+use alloc::vec::Vec;
+use dioptre::Field;
+
+use crate::{plain_array, Allocator, Columns, Global, RawTable, TableSlice, TableSliceMut};
+
+/// A growable struct of arrays made of fixed-capacity chunks, whose rows never move.
+///
+/// Where a [`Table`](crate::Table) reallocates and copies its rows as it grows, a `ChunkedTable`
+/// appends a new [`RawTable`] chunk whenever its last one is full. Rows are addressed by the index
+/// of their chunk and their index within it, and each chunk holds a contiguous run of each column.
+/// Pointers into a chunk stay valid as more rows are appended.
+pub struct ChunkedTable<T: Columns, A: Allocator + Clone = Global> {
+    chunks: Vec<RawTable<T, A>>,
+    chunk_capacity: usize,
+    len: usize,
+    alloc: A,
+}
+
+impl<T: Columns> ChunkedTable<T> {
+    /// Create an empty `ChunkedTable` whose chunks each hold `chunk_capacity` rows, without
+    /// allocating.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_capacity` is zero, or if a chunk would exceed [`usize::MAX`] bytes.
+    pub fn new(chunk_capacity: usize) -> Self { Self::new_in(chunk_capacity, Global) }
+}
+
+impl<T: Columns, A: Allocator + Clone> ChunkedTable<T, A> {
+    /// Create an empty `ChunkedTable` in `alloc` whose chunks each hold `chunk_capacity` rows,
+    /// without allocating.
+    ///
+    /// Like a [`RawTable`]'s capacity, `chunk_capacity` is rounded up so each field array ends on a
+    /// [`Columns::ALIGN`] boundary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_capacity` is zero, or if a chunk would exceed [`usize::MAX`] bytes.
+    pub fn new_in(chunk_capacity: usize, alloc: A) -> Self {
+        assert!(chunk_capacity > 0, "chunk capacity must be non-zero");
+        let chunk_capacity = RawTable::<T, A>::pad(chunk_capacity).expect("capacity overflow");
+        ChunkedTable { chunks: Vec::new(), chunk_capacity, len: 0, alloc }
+    }
+
+    /// Get a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A { &self.alloc }
+
+    /// Get the number of rows.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Check whether the table contains no rows.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Get the number of rows each chunk can hold.
+    pub fn chunk_capacity(&self) -> usize { self.chunk_capacity }
+
+    /// Get the number of chunks. Only the last one can have fewer than `chunk_capacity` rows.
+    pub fn chunk_count(&self) -> usize { self.chunks.len() }
+
+    /// Get the number of rows in chunk `chunk`, or zero if it does not exist.
+    pub fn chunk_len(&self, chunk: usize) -> usize {
+        chunk_len(self.len, self.chunk_capacity, chunk)
+    }
+
+    /// Append a row to the table, returning its chunk and its index within that chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a new chunk would exceed [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn push(&mut self, row: T) -> (usize, usize) {
+        let (chunk, index) = (self.len / self.chunk_capacity, self.len % self.chunk_capacity);
+        if chunk == self.chunks.len() {
+            let alloc = self.alloc.clone();
+            self.chunks.push(RawTable::with_capacity_in(self.chunk_capacity, alloc));
+        }
+
+        unsafe { self.chunks[chunk].write(index, row); }
+        self.len += 1;
+        (chunk, index)
+    }
+
+    /// Remove the last row and return it, or `None` if the table is empty. The last chunk is freed
+    /// once it is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.len -= 1;
+        let (chunk, index) = (self.len / self.chunk_capacity, self.len % self.chunk_capacity);
+        let row = unsafe { self.chunks[chunk].read(index) };
+        if index == 0 {
+            self.chunks.pop();
+        }
+        Some(row)
+    }
+
+    /// Remove all rows from the table, dropping them and freeing every chunk.
+    pub fn clear(&mut self) {
+        // Empty the table first, so a panicking destructor leaks the remaining rows rather than
+        // dropping them twice.
+        let len = mem::replace(&mut self.len, 0);
+        let mut chunks = mem::take(&mut self.chunks);
+        if mem::needs_drop::<T>() {
+            for (chunk, raw) in chunks.iter_mut().enumerate() {
+                for index in 0..chunk_len(len, self.chunk_capacity, chunk) {
+                    unsafe { drop(raw.read(index)); }
+                }
+            }
+        }
+    }
+
+    /// Borrow the fields of row `index` of chunk `chunk`, or `None` if it does not exist.
+    pub fn get(&self, chunk: usize, index: usize) -> Option<T::Ref<'_>> {
+        if index >= self.chunk_len(chunk) {
+            return None;
+        }
+
+        unsafe { Some(T::row(self.chunks[chunk].pointers.borrow(), index)) }
+    }
+
+    /// Mutably borrow the fields of row `index` of chunk `chunk`, or `None` if it does not exist.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM, allocating the chunk's `#[soak(lazy)]` arrays.
+    pub fn get_mut(&mut self, chunk: usize, index: usize) -> Option<T::Mut<'_>> {
+        if index >= self.chunk_len(chunk) {
+            return None;
+        }
+
+        let raw = &mut self.chunks[chunk];
+        raw.allocate_all();
+        unsafe { Some(T::row_mut(raw.pointers.borrow(), index)) }
+    }
+
+    /// Get a pointer to a field array of chunk `chunk`.
+    ///
+    /// The pointer stays valid until the chunk is freed by [`pop`](ChunkedTable::pop) or
+    /// [`clear`](ChunkedTable::clear), however many rows are appended. Only the first
+    /// [`chunk_len`](ChunkedTable::chunk_len) elements of the array are initialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk` is out of bounds, or if the field is not stored as a plain array of its
+    /// own type.
+    pub fn ptr<F>(&mut self, chunk: usize, field: Field<T, F>) -> *mut F {
+        self.chunks[chunk].ptr(field)
+    }
+
+    /// Borrow the rows of chunk `chunk`, or `None` if it does not exist.
+    pub fn chunk(&self, chunk: usize) -> Option<TableSlice<'_, T>> {
+        let raw = self.chunks.get(chunk)?;
+        unsafe { Some(TableSlice::from_raw_parts(raw.pointers, 0, self.chunk_len(chunk))) }
+    }
+
+    /// Mutably borrow the rows of chunk `chunk`, or `None` if it does not exist.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM, allocating the chunk's `#[soak(lazy)]` arrays.
+    pub fn chunk_mut(&mut self, chunk: usize) -> Option<TableSliceMut<'_, T>> {
+        let len = self.chunk_len(chunk);
+        let raw = self.chunks.get_mut(chunk)?;
+        raw.allocate_all();
+        unsafe { Some(TableSliceMut::from_raw_parts(raw.pointers, 0, len)) }
+    }
+
+    /// Iterate over the chunks' rows.
+    pub fn chunks(&self)
+        -> impl DoubleEndedIterator<Item = TableSlice<'_, T>> + ExactSizeIterator
+    {
+        let (len, chunk_capacity) = (self.len, self.chunk_capacity);
+        self.chunks.iter().enumerate().map(move |(chunk, raw)| unsafe {
+            TableSlice::from_raw_parts(raw.pointers, 0, chunk_len(len, chunk_capacity, chunk))
+        })
+    }
+
+    /// Iterate over the chunks' rows mutably.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM, allocating the chunks' `#[soak(lazy)]` arrays.
+    pub fn chunks_mut(&mut self)
+        -> impl DoubleEndedIterator<Item = TableSliceMut<'_, T>> + ExactSizeIterator
+    {
+        let (len, chunk_capacity) = (self.len, self.chunk_capacity);
+        self.chunks.iter_mut().for_each(RawTable::allocate_all);
+        self.chunks.iter_mut().enumerate().map(move |(chunk, raw)| unsafe {
+            TableSliceMut::from_raw_parts(raw.pointers, 0, chunk_len(len, chunk_capacity, chunk))
+        })
+    }
+
+    /// Iterate over the runs of a field array held by each chunk, in row order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn column<'a, F: 'a>(&'a self, field: Field<T, F>)
+        -> impl DoubleEndedIterator<Item = &'a [F]> + ExactSizeIterator
+    {
+        let array = plain_array::<T>(field.index());
+        let (len, chunk_capacity) = (self.len, self.chunk_capacity);
+        self.chunks.iter().enumerate().map(move |(chunk, raw)| unsafe {
+            let data = raw.pointers.borrow()[array].as_ptr() as *const F;
+            slice::from_raw_parts(data, chunk_len(len, chunk_capacity, chunk))
+        })
+    }
+
+    /// Iterate over the runs of a field array held by each chunk mutably, in row order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn column_mut<'a, F: 'a>(&'a mut self, field: Field<T, F>)
+        -> impl DoubleEndedIterator<Item = &'a mut [F]> + ExactSizeIterator
+    {
+        let array = plain_array::<T>(field.index());
+        let (len, chunk_capacity) = (self.len, self.chunk_capacity);
+        self.chunks.iter_mut().enumerate().map(move |(chunk, raw)| unsafe {
+            let data = raw.pointers.borrow()[array].as_ptr() as *mut F;
+            slice::from_raw_parts_mut(data, chunk_len(len, chunk_capacity, chunk))
+        })
+    }
+
+    /// Iterate over borrowed rows, in row order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = T::Ref<'_>> {
+        self.chunks().flat_map(TableSlice::into_iter)
+    }
+
+    /// Iterate over mutably borrowed rows, in row order.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM, allocating the chunks' `#[soak(lazy)]` arrays.
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = T::Mut<'_>> {
+        self.chunks_mut().flat_map(TableSliceMut::into_iter)
+    }
+}
+
+impl<T: Columns, A: Allocator + Clone> Drop for ChunkedTable<T, A> {
+    /// Drop the rows. Each `RawTable` then frees its chunk.
+    fn drop(&mut self) { self.clear() }
+}
+
+impl<T: Columns, A: Allocator + Clone> Extend<T> for ChunkedTable<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|row| { self.push(row); });
+    }
+}
+
+/// Get the number of rows in chunk `chunk` of a table of `len` rows.
+fn chunk_len(len: usize, chunk_capacity: usize, chunk: usize) -> usize {
+    cmp::min(chunk_capacity, len.saturating_sub(chunk.saturating_mul(chunk_capacity)))
+}
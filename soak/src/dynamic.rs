@@ -0,0 +1,379 @@
+use core::{cmp, fmt, mem, ptr, slice};
+use core::any::TypeId;
+use alloc::alloc::Layout;
+use alloc::{boxed::Box, vec, vec::Vec};
+
+use crate::{arrays_layout, handle_reserve, place_arrays, Allocator, Global, TryReserveError};
+
+/// A description of one column of a [`DynTable`]: the layout and drop glue of its elements, and
+/// optionally their type.
+#[derive(Copy, Clone)]
+pub struct Column {
+    layout: Layout,
+    drop: Option<unsafe fn(*mut u8)>,
+    type_id: Option<TypeId>,
+}
+
+impl Column {
+    /// Describe a column of `F`s, whose typed accessors are checked against `F`.
+    ///
+    /// `F` must be `Send` and `Sync` so that a [`DynTable`] can be shared between threads.
+    pub fn of<F: Send + Sync + 'static>() -> Self {
+        unsafe fn drop_erased<F>(data: *mut u8) { ptr::drop_in_place(data as *mut F) }
+
+        let drop = if mem::needs_drop::<F>() {
+            Some(drop_erased::<F> as unsafe fn(_))
+        } else {
+            None
+        };
+        Column { layout: Layout::new::<F>(), drop, type_id: Some(TypeId::of::<F>()) }
+    }
+
+    /// Describe a column of untyped elements with `layout`, dropped by `drop` if present.
+    ///
+    /// Typed accessors always return `None` for the resulting column.
+    ///
+    /// # Safety
+    ///
+    /// `layout.size()` must be a multiple of `layout.align()`, as it is for any Rust type. `drop`
+    /// must be safe to call on a pointer to any initialized element of the column. The elements
+    /// must be safe to send and share between threads, as if they were `Send` and `Sync`.
+    pub unsafe fn new(layout: Layout, drop: Option<unsafe fn(*mut u8)>) -> Self {
+        Column { layout, drop, type_id: None }
+    }
+
+    /// Get the layout of one element.
+    pub fn layout(&self) -> Layout { self.layout }
+
+    /// Get the size of one element.
+    pub fn size(&self) -> usize { self.layout.size() }
+
+    /// Get the alignment of one element.
+    pub fn align(&self) -> usize { self.layout.align() }
+
+    /// Get the type of the column's elements, if it was created with [`Column::of`].
+    pub fn type_id(&self) -> Option<TypeId> { self.type_id }
+
+    /// Check whether the column's elements need to be dropped.
+    pub fn needs_drop(&self) -> bool { self.drop.is_some() }
+
+    /// Check whether the column holds elements of type `F`.
+    pub fn is<F: 'static>(&self) -> bool { self.type_id == Some(TypeId::of::<F>()) }
+}
+
+impl fmt::Debug for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Column")
+            .field("layout", &self.layout)
+            .field("needs_drop", &self.needs_drop())
+            .field("type_id", &self.type_id)
+            .finish()
+    }
+}
+
+/// A raw allocation containing parallel arrays described at runtime by a list of [`Column`]s.
+///
+/// This is the type-erased counterpart of [`RawTable`](crate::RawTable), for schemas that are not
+/// known at compile time. Like `RawTable`, it shares a single allocation between its columns, and
+/// leaves tracking which rows are initialized to its owner. Column `index` is addressed by its
+/// position in the list the table was created with.
+///
+/// Every column's elements are `Send` and `Sync`, so a `DynTable` is too if its allocator is.
+pub struct DynTable<A: Allocator = Global> {
+    columns: Box<[Column]>,
+    pointers: Box<[ptr::NonNull<u8>]>,
+    capacity: usize,
+    alloc: A,
+}
+
+unsafe impl<A: Allocator + Send> Send for DynTable<A> {}
+unsafe impl<A: Allocator + Sync> Sync for DynTable<A> {}
+
+impl DynTable {
+    /// Create a `DynTable` with `columns` without allocating.
+    pub fn new(columns: Vec<Column>) -> Self { Self::new_in(columns, Global) }
+
+    /// Create a `DynTable` with `columns` and enough space for `capacity` elements of each.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn with_capacity(columns: Vec<Column>, capacity: usize) -> Self {
+        Self::with_capacity_in(columns, capacity, Global)
+    }
+
+    /// Create a `DynTable` with `columns` and enough space for `capacity` elements of each,
+    /// returning an error if the allocation fails.
+    pub fn try_with_capacity(columns: Vec<Column>, capacity: usize)
+        -> Result<Self, TryReserveError>
+    {
+        Self::try_with_capacity_in(columns, capacity, Global)
+    }
+}
+
+impl<A: Allocator> DynTable<A> {
+    /// Create a `DynTable` with `columns` in `alloc` without allocating.
+    pub fn new_in(columns: Vec<Column>, alloc: A) -> Self {
+        let columns = columns.into_boxed_slice();
+        let align = Self::align(&columns);
+        let dangling = unsafe { ptr::NonNull::new_unchecked(align as *mut u8) };
+        let pointers = vec![dangling; columns.len()].into_boxed_slice();
+        let capacity = if Self::is_zero_sized(&columns) { usize::MAX } else { 0 };
+        DynTable { columns, pointers, capacity, alloc }
+    }
+
+    /// Create a `DynTable` with `columns` in `alloc` and enough space for `capacity` elements of
+    /// each.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn with_capacity_in(columns: Vec<Column>, capacity: usize, alloc: A) -> Self {
+        handle_reserve(Self::try_with_capacity_in(columns, capacity, alloc))
+    }
+
+    /// Create a `DynTable` with `columns` in `alloc` and enough space for `capacity` elements of
+    /// each, returning an error if the allocation fails.
+    pub fn try_with_capacity_in(columns: Vec<Column>, capacity: usize, alloc: A)
+        -> Result<Self, TryReserveError>
+    {
+        let mut table = Self::new_in(columns, alloc);
+        table.try_reserve_exact(0, capacity)?;
+        Ok(table)
+    }
+
+    /// Get a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A { &self.alloc }
+
+    /// Get the descriptors of the table's columns.
+    pub fn columns(&self) -> &[Column] { &self.columns }
+
+    /// Get the capacity of the allocation.
+    pub fn capacity(&self) -> usize { self.capacity }
+
+    /// Get an untyped pointer to column `index`'s array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn ptr(&mut self, index: usize) -> *mut u8 { self.pointers[index].as_ptr() }
+
+    /// Get a pointer to column `index`'s array, or `None` if it does not hold `F`s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn typed_ptr<F: 'static>(&mut self, index: usize) -> Option<*mut F> {
+        if !self.columns[index].is::<F>() {
+            return None;
+        }
+
+        Some(self.pointers[index].as_ptr() as *mut F)
+    }
+
+    /// Borrow the first `used` elements of column `index`, or `None` if it does not hold `F`s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Safety
+    ///
+    /// `used` must be no greater than the table's capacity, and those elements must be
+    /// initialized.
+    pub unsafe fn column<F: 'static>(&self, index: usize, used: usize) -> Option<&[F]> {
+        if !self.columns[index].is::<F>() {
+            return None;
+        }
+
+        Some(slice::from_raw_parts(self.pointers[index].as_ptr() as *const F, used))
+    }
+
+    /// Mutably borrow the first `used` elements of column `index`, or `None` if it does not hold
+    /// `F`s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Safety
+    ///
+    /// `used` must be no greater than the table's capacity, and those elements must be
+    /// initialized.
+    pub unsafe fn column_mut<F: 'static>(&mut self, index: usize, used: usize)
+        -> Option<&mut [F]>
+    {
+        let data = self.typed_ptr::<F>(index)?;
+        Some(slice::from_raw_parts_mut(data, used))
+    }
+
+    /// Copy `count` elements of each column from index `src` to index `dst`.
+    ///
+    /// The source and destination may overlap. Like [`ptr::copy`], this leaves the source elements
+    /// initialized and overwrites the destination elements without dropping them.
+    ///
+    /// # Safety
+    ///
+    /// Both `src + count` and `dst + count` must be no greater than the table's capacity.
+    pub unsafe fn copy(&mut self, src: usize, dst: usize, count: usize) {
+        for (pointer, column) in Iterator::zip(self.pointers.iter(), self.columns.iter()) {
+            let (data, size) = (pointer.as_ptr(), column.size());
+            ptr::copy(data.add(src * size), data.add(dst * size), count * size);
+        }
+    }
+
+    /// Drop `count` elements of each column, starting at index `start`.
+    ///
+    /// # Safety
+    ///
+    /// `start + count` must be no greater than the table's capacity, and those elements must be
+    /// initialized. They are left logically uninitialized.
+    pub unsafe fn drop_in_place(&mut self, start: usize, count: usize) {
+        for (pointer, column) in Iterator::zip(self.pointers.iter(), self.columns.iter()) {
+            if let Some(drop) = column.drop {
+                let size = column.size();
+                for index in start..start + count {
+                    drop(pointer.as_ptr().add(index * size));
+                }
+            }
+        }
+    }
+
+    /// Ensure that the table contains enough space for `used + extra` elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn reserve_exact(&mut self, used: usize, extra: usize) {
+        handle_reserve(self.try_reserve_exact(used, extra))
+    }
+
+    /// Ensure that the table contains enough space for `used + extra` elements, returning an error
+    /// if the allocation fails.
+    pub fn try_reserve_exact(&mut self, used: usize, extra: usize) -> Result<(), TryReserveError> {
+        if used <= self.capacity && self.capacity - used >= extra {
+            return Ok(());
+        }
+
+        let capacity = usize::checked_add(used, extra).ok_or(TryReserveError::CapacityOverflow)?;
+        self.relayout(cmp::min(used, self.capacity), capacity)
+    }
+
+    /// Ensure that the table contains enough space for `used + extra` elements, growing it
+    /// geometrically to amortize the cost of repeated calls.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn reserve(&mut self, used: usize, extra: usize) {
+        handle_reserve(self.try_reserve(used, extra))
+    }
+
+    /// Ensure that the table contains enough space for `used + extra` elements, growing it
+    /// geometrically and returning an error if the allocation fails.
+    pub fn try_reserve(&mut self, used: usize, extra: usize) -> Result<(), TryReserveError> {
+        if used <= self.capacity && self.capacity - used >= extra {
+            return Ok(());
+        }
+
+        let required = usize::checked_add(used, extra).ok_or(TryReserveError::CapacityOverflow)?;
+        let capacity = cmp::max(cmp::max(self.capacity.saturating_mul(2), required), 4);
+        self.relayout(cmp::min(used, self.capacity), capacity)
+    }
+
+    /// Shrink the table's capacity as close as possible to `max(used, capacity)`.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn shrink_to(&mut self, used: usize, capacity: usize) {
+        let capacity = cmp::max(used, capacity);
+        if Self::is_zero_sized(&self.columns) || capacity >= self.capacity {
+            return;
+        }
+
+        handle_reserve(self.relayout(used, capacity))
+    }
+
+    /// Shrink the table's capacity as close as possible to `used`.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn shrink_to_fit(&mut self, used: usize) { self.shrink_to(used, used) }
+
+    /// Compute the alignment of the allocation.
+    fn align(columns: &[Column]) -> usize {
+        columns.iter().fold(1, |align, column| cmp::max(align, column.align()))
+    }
+
+    /// Check whether every column's elements are zero-sized.
+    fn is_zero_sized(columns: &[Column]) -> bool {
+        columns.iter().all(|column| column.size() == 0)
+    }
+
+    /// Compute the layout of an allocation for `capacity` elements, or `None` on overflow.
+    fn layout(&self, capacity: usize) -> Option<Layout> {
+        let lens = self.columns.iter().map(|column| usize::checked_mul(capacity, column.size()));
+        arrays_layout(lens, Self::align(&self.columns))
+    }
+
+    /// Move the first `used` elements of each column into a new allocation of `capacity`.
+    fn relayout(&mut self, used: usize, capacity: usize) -> Result<(), TryReserveError> {
+        unsafe {
+            let layout = self.layout(capacity).ok_or(TryReserveError::CapacityOverflow)?;
+            let data = if layout.size() == 0 {
+                layout.align() as *mut u8
+            } else {
+                self.alloc.alloc(layout)
+            };
+            if data.is_null() {
+                return Err(TryReserveError::AllocError { layout });
+            }
+
+            let mut pointers = self.pointers.clone();
+            let lens = self.columns.iter().map(|column| capacity * column.size());
+            place_arrays(data, lens, layout.align(), &mut pointers);
+
+            let columns = self.columns.iter();
+            let arrays = Iterator::zip(self.pointers.iter(), pointers.iter());
+            for ((src, dst), column) in Iterator::zip(arrays, columns) {
+                ptr::copy_nonoverlapping(src.as_ptr(), dst.as_ptr(), used * column.size());
+            }
+
+            self.deallocate();
+            self.pointers = pointers;
+            self.capacity = capacity;
+            Ok(())
+        }
+    }
+
+    /// Free the column arrays without dropping their elements.
+    unsafe fn deallocate(&mut self) {
+        let layout = self.layout(self.capacity).unwrap_unchecked();
+        if layout.size() > 0 { self.alloc.dealloc(self.pointers[0].as_ptr(), layout); }
+    }
+}
+
+impl<A: Allocator> Drop for DynTable<A> {
+    /// Free the underlying buffer but do not drop the columns' elements.
+    fn drop(&mut self) {
+        unsafe { self.deallocate() }
+    }
+}
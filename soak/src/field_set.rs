@@ -0,0 +1,93 @@
+use core::{ptr, slice};
+use dioptre::Field;
+
+use crate::{plain_array, Columns};
+
+/// A tuple of distinct [`Field`]s of `T`, whose arrays can be borrowed together.
+///
+/// This is implemented for tuples of up to eight `Field`s, and is used by methods like
+/// [`Table::columns_mut`](crate::Table::columns_mut) to hand out several mutable columns at once:
+///
+/// ```
+/// # use dioptre::Fields;
+/// # use soak::{Columns, Table};
+/// # #[derive(Fields, Columns)]
+/// # struct GameObject {
+/// #     position: (f32, f32),
+/// #     velocity: (f32, f32),
+/// #     health: f32,
+/// # }
+/// fn step(table: &mut Table<GameObject>) {
+///     let fields = (GameObject::position, GameObject::velocity);
+///     let (positions, velocities) = table.columns_mut(fields);
+///     for (position, velocity) in Iterator::zip(positions.iter_mut(), velocities.iter()) {
+///         position.0 += velocity.0;
+///         position.1 += velocity.1;
+///     }
+/// }
+/// ```
+///
+/// # Safety
+///
+/// * `ptrs` must panic unless the fields are pairwise distinct, and each is stored as a plain
+///   array of its own type.
+/// * `slices` must only offset the pointers produced by `ptrs`.
+pub unsafe trait FieldSet<T: Columns>: Sized {
+    /// A tuple of pointers to the fields' arrays.
+    type Ptrs: Copy;
+    /// A tuple of mutable slices of the fields' arrays.
+    type Slices<'a> where Self: 'a;
+
+    /// Select the fields' arrays from `pointers`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any field appears more than once, or is not stored as a plain array.
+    fn ptrs(self, pointers: &[ptr::NonNull<u8>]) -> Self::Ptrs;
+
+    /// Borrow elements `start..start + len` of each array.
+    ///
+    /// # Safety
+    ///
+    /// The elements must be initialized, and not otherwise borrowed for `'a`.
+    unsafe fn slices<'a>(ptrs: Self::Ptrs, start: usize, len: usize) -> Self::Slices<'a>
+    where Self: 'a;
+}
+
+/// Panic unless `indices` are pairwise distinct.
+fn assert_disjoint(indices: &[usize]) {
+    for (i, index) in indices.iter().enumerate() {
+        assert!(!indices[..i].contains(index), "field borrowed more than once");
+    }
+}
+
+macro_rules! field_set {
+    ($($field:ident: $ty:ident),*) => {
+        unsafe impl<T: Columns, $($ty),*> FieldSet<T> for ($(Field<T, $ty>,)*) {
+            type Ptrs = ($(*mut $ty,)*);
+            type Slices<'a> = ($(&'a mut [$ty],)*) where Self: 'a;
+
+            fn ptrs(self, pointers: &[ptr::NonNull<u8>]) -> Self::Ptrs {
+                let ($($field,)*) = self;
+                let [$($field,)*] = [$(plain_array::<T>($field.index()),)*];
+                assert_disjoint(&[$($field,)*]);
+                ($(pointers[$field].as_ptr() as *mut $ty,)*)
+            }
+
+            unsafe fn slices<'a>(ptrs: Self::Ptrs, start: usize, len: usize) -> Self::Slices<'a>
+            where Self: 'a {
+                let ($($field,)*) = ptrs;
+                ($(slice::from_raw_parts_mut($field.add(start), len),)*)
+            }
+        }
+    };
+}
+
+field_set!(a: A);
+field_set!(a: A, b: B);
+field_set!(a: A, b: B, c: C);
+field_set!(a: A, b: B, c: C, d: D);
+field_set!(a: A, b: B, c: C, d: D, e: E);
+field_set!(a: A, b: B, c: C, d: D, e: E, f: F);
+field_set!(a: A, b: B, c: C, d: D, e: E, f: F, g: G);
+field_set!(a: A, b: B, c: C, d: D, e: E, f: F, g: G, h: H);
@@ -45,33 +45,566 @@
 //!     }
 //! }
 //! ```
+//!
+//! Most code will not need to manage a `RawTable`'s contents by hand. The [`Table`] type tracks
+//! which rows are initialized, much like a [`Vec`], and moves whole structs in and out of its
+//! columns. Its columns can then be accessed as slices:
+//!
+//! ```
+//! # use dioptre::Fields;
+//! # use soak::{Columns, Table};
+//! # #[derive(Fields, Columns)]
+//! # struct GameObject {
+//! #     position: (f32, f32),
+//! #     velocity: (f32, f32),
+//! #     health: f32,
+//! # }
+//! let mut table = Table::new();
+//! table.push(GameObject { position: (0.0, 0.0), velocity: (1.0, 0.0), health: 100.0 });
+//!
+//! for health in table.column_mut(GameObject::health) {
+//!     *health -= 10.0;
+//! }
+//! assert_eq!(table.column(GameObject::health), &[90.0]);
+//! ```
 
 #![no_std]
 
 extern crate alloc;
 
-use core::{mem, ptr, usize};
+use core::{cmp, fmt, mem, ptr};
 use core::borrow::{Borrow, BorrowMut};
 use core::marker::PhantomData;
-use alloc::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use alloc::alloc::{handle_alloc_error, Layout};
+use alloc::vec;
 use dioptre::{Fields, Field};
 
 pub use soak_derive::Columns;
+pub use allocator::{Allocator, Global};
+pub use aosoa::AoSoATable;
+pub use bits::{BitColumn, BitColumnMut, BitMut};
+pub use chunked::ChunkedTable;
+pub use dynamic::{Column, DynTable};
+pub use field_set::FieldSet;
+pub use nullable::{NullableColumn, NullableColumnMut, NullableMut};
+pub use slice::{Chunks, ChunksMut, TableSlice, TableSliceMut};
+pub use slot::{Handle, SlotTable};
+pub use table::{Drain, IntoIter, Iter, IterMut, Table};
+
+pub mod archetype;
+
+mod allocator;
+mod aosoa;
+mod bits;
+mod chunked;
+mod dynamic;
+mod field_set;
+mod nullable;
+mod slice;
+mod slot;
+mod table;
+#[cfg(feature = "rayon")]
+mod par;
 
 /// Metadata required to use a struct in a [`RawTable`].
 ///
 /// This trait should not normally be implemented by hand. Instead, use `#[derive(Columns)]`- this
 /// will safely generate the appropriate trait impl.
 ///
+/// By default, each field array is aligned only as much as its field type requires. Vectorized
+/// code can instead request a larger alignment for every array with `#[soak(align = N)]`:
+///
+/// ```
+/// # use dioptre::Fields;
+/// # use soak::{Columns, RawTable};
+/// #[derive(Fields, Columns)]
+/// #[soak(align = 32)]
+/// struct Particle {
+///     x: f32,
+///     y: f32,
+///     mass: f64,
+/// }
+///
+/// let mut table = RawTable::<Particle>::with_capacity(10);
+/// assert_eq!(table.ptr(Particle::y) as usize % 32, 0);
+/// assert_eq!(table.capacity(), 16);
+/// ```
+///
+/// A field of type `Option<F>` can instead be marked `#[soak(nullable)]`. Its column then holds
+/// plain `F`s, alongside a packed bitset recording which rows hold a value. Row views borrow it as
+/// an `Option<&F>` or a [`NullableMut`], and the whole column is available through
+/// [`Table::nullable`]:
+///
+/// ```
+/// # use dioptre::Fields;
+/// # use soak::{Columns, Table};
+/// #[derive(Fields, Columns)]
+/// struct Sample {
+///     time: f64,
+///     #[soak(nullable)]
+///     reading: Option<f32>,
+/// }
+///
+/// let mut table = Table::new();
+/// table.push(Sample { time: 0.0, reading: Some(1.5) });
+/// table.push(Sample { time: 1.0, reading: None });
+/// assert_eq!(table.get(0).unwrap().reading, Some(&1.5));
+/// assert_eq!(table.nullable(Sample::reading).iter().collect::<Vec<_>>(), [Some(&1.5), None]);
+/// ```
+///
+/// A field whose type also implements `Columns` can be marked `#[dioptre(flatten)]`, to store each
+/// of its own fields in a separate array. The field's handle addresses the whole group of columns
+/// through [`Table::group`], and can be composed with its type's handles to address one of them:
+///
+/// ```
+/// # use dioptre::Fields;
+/// # use soak::{Columns, Table};
+/// #[derive(Fields, Columns)]
+/// struct Vec2 {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// #[derive(Fields, Columns)]
+/// struct Body {
+///     #[dioptre(flatten)]
+///     position: Vec2,
+///     mass: f32,
+/// }
+///
+/// let mut table = Table::new();
+/// table.push(Body { position: Vec2 { x: 1.0, y: 2.0 }, mass: 3.0 });
+/// assert_eq!(table.get(0).unwrap().position.y, &2.0);
+/// assert_eq!(table.column(Body::position.then(Vec2::x)), &[1.0]);
+/// assert_eq!(table.group(Body::position).column(Vec2::y), &[2.0]);
+/// ```
+///
+/// Fields that are rarely accessed can be marked `#[soak(cold)]`. Their arrays are then kept in a
+/// separate allocation, away from the others, but are otherwise accessed in the same way:
+///
+/// ```
+/// # use dioptre::Fields;
+/// # use soak::{Columns, Table};
+/// #[derive(Fields, Columns)]
+/// struct Particle {
+///     position: (f32, f32),
+///     velocity: (f32, f32),
+///     #[soak(cold)]
+///     name: &'static str,
+/// }
+///
+/// let mut table = Table::new();
+/// table.push(Particle { position: (0.0, 0.0), velocity: (1.0, 1.0), name: "spark" });
+/// assert_eq!(table.column(Particle::name), &["spark"]);
+/// ```
+///
+/// A field that is often never used can be marked `#[soak(lazy)]`, so its array is only allocated
+/// the first time it is needed. Until then, the column costs no memory. `lazy` does not change how
+/// the field is stored: like any other field, it is a plain array of its declared type, even if
+/// that type is an `Option`. A [`RawTable`] allocates a lazy field's array the first time a row is
+/// written, or the array is accessed through [`RawTable::ptr`]. This means that `ptr` can allocate
+/// and so abort on OOM, unlike the same call for any other field.
+///
+/// A field marked `#[soak(lazy, nullable)]` is instead stored like a nullable field, and its
+/// arrays are only allocated once a row holding a value is written, or the rows are borrowed
+/// mutably, as through [`Table::nullable_mut`] or [`Table::get_mut`]. Until then, every row reads
+/// as `None`:
+///
+/// ```
+/// # use dioptre::Fields;
+/// # use soak::{Columns, Table};
+/// #[derive(Fields, Columns)]
+/// struct Entity {
+///     health: f32,
+///     #[soak(lazy, nullable)]
+///     label: Option<&'static str>,
+/// }
+///
+/// let mut table = Table::new();
+/// table.push(Entity { health: 1.0, label: None });
+/// assert!(!table.is_allocated(Entity::label));
+/// assert_eq!(table.get(0).unwrap().label, None);
+///
+/// table.nullable_mut(Entity::label).replace(0, Some("player"));
+/// assert!(table.is_allocated(Entity::label));
+/// assert_eq!(table.get(0).unwrap().label, Some(&"player"));
+/// ```
+///
+/// Fields of type `bool` are packed into a bitset, one bit per row. A field whose type is `bool`
+/// under another name can be packed by marking it `#[soak(bits)]`. Row views hold the field as a
+/// `bool` or a [`BitMut`], and the whole column is available through [`Table::bits`], which can
+/// be scanned a word at a time:
+///
+/// ```
+/// # use dioptre::Fields;
+/// # use soak::{Columns, Table};
+/// #[derive(Fields, Columns)]
+/// struct Cell {
+///     id: u32,
+///     alive: bool,
+/// }
+///
+/// let mut table = Table::new();
+/// (0..100).for_each(|id| table.push(Cell { id, alive: id % 4 == 0 }));
+/// table.get_mut(1).unwrap().alive.set(true);
+/// assert_eq!(table.bits(Cell::alive).count_ones(), 26);
+/// ```
+///
 /// # Safety
 ///
-/// * `Pointers` must be a fixed-size array matching `Fields::SIZES` and `Fields::ALIGNS` in length.
-/// * `dangling()` must contain `ptr::NonNull::dangling()`.
+/// * `Pointers` must be a fixed-size array matching `ARRAYS`, `COLD` and `LAZY` in length.
+/// * `STORAGE` must have one entry for each of `Fields`' elements, and must describe arrays that
+///   hold the field's values. No two fields may share an array, except within a flattened field.
+/// * Fields stored as [`Storage::Bits`] must have type `bool` and an [`Array::Bits`] array, and
+///   the validity bitsets of `Nullable` fields must be [`Array::Bits`] arrays.
+/// * The arrays of a [`Storage::Flatten`] field must match its type's `ARRAYS`, and the field's
+///   elements must be stored as described by its type's `STORAGE`, at the same offset.
+/// * Only `Plain` arrays and the values and validity bitsets of `Nullable` fields may be `LAZY`,
+///   and a `Nullable` field's arrays must both be or neither be. `needs` must return `true` for a
+///   `LAZY` `Plain` array, and for a `Nullable` field's arrays whenever it holds a value.
+/// * `dangling()` must contain `ptr::NonNull::dangling()`, aligned for each array.
+/// * `ALIGN` must be a power of two.
+/// * `write` and `read` must move each field to or from element `index` of its own arrays.
+/// * `row` and `row_mut` must borrow each field from element `index` of its own arrays, and may
+///   only access bits through atomic operations.
+/// * A `LAZY` field whose validity bitset is still dangling must be treated as empty in every row,
+///   and must not be written to unless it is empty.
 pub unsafe trait Columns: Fields {
-    /// A fixed-size array of pointers to field arrays.
-    type Pointers: BorrowMut<[ptr::NonNull<u8>]>;
+    /// A fixed-size array of pointers to arrays.
+    type Pointers: BorrowMut<[ptr::NonNull<u8>]> + Copy;
     /// An empty value for `Self::Pointers`.
     fn dangling() -> Self::Pointers;
+
+    /// The arrays that make up a table, one for each pointer.
+    const ARRAYS: &'static [Array];
+    /// Whether each array is stored in the table's separate cold allocation.
+    const COLD: &'static [bool];
+    /// Whether each array is allocated separately, the first time it is needed.
+    const LAZY: &'static [bool];
+    /// How each field is stored in the arrays.
+    const STORAGE: &'static [Storage];
+
+    /// The minimum alignment of each field array.
+    ///
+    /// When this exceeds a field's own alignment, tables also round their capacity up, so that
+    /// each field array ends on an `ALIGN` boundary.
+    const ALIGN: usize = 1;
+
+    /// Shared references to the fields of one row, generated as `{Self}Ref`.
+    type Ref<'a>: Copy where Self: 'a;
+    /// Mutable references to the fields of one row, generated as `{Self}Mut`.
+    type Mut<'a> where Self: 'a;
+
+    /// Check whether moving `self` into a table needs the `LAZY` array `array` to be allocated.
+    fn needs(&self, _array: usize) -> bool { true }
+
+    /// Move `self` into element `index` of the field arrays at `pointers`, one field at a time.
+    ///
+    /// # Safety
+    ///
+    /// `pointers` must point to field arrays laid out for `Self`, with space for at least
+    /// `index + 1` elements. Any values already at `index` are overwritten without being dropped.
+    unsafe fn write(self, pointers: &[ptr::NonNull<u8>], index: usize);
+
+    /// Move the fields at element `index` out of the field arrays at `pointers`, reassembling them
+    /// into a `Self`.
+    ///
+    /// # Safety
+    ///
+    /// `pointers` must point to field arrays laid out for `Self`, with initialized elements at
+    /// `index`. The elements are left logically uninitialized.
+    unsafe fn read(pointers: &[ptr::NonNull<u8>], index: usize) -> Self;
+
+    /// Borrow the fields at element `index` of the field arrays at `pointers`.
+    ///
+    /// # Safety
+    ///
+    /// `pointers` must point to field arrays laid out for `Self`, with initialized elements at
+    /// `index`. They must not be mutated for `'a`.
+    unsafe fn row<'a>(pointers: &[ptr::NonNull<u8>], index: usize) -> Self::Ref<'a>
+    where Self: 'a;
+
+    /// Mutably borrow the fields at element `index` of the field arrays at `pointers`.
+    ///
+    /// # Safety
+    ///
+    /// `pointers` must point to field arrays laid out for `Self`, with initialized elements at
+    /// `index`, and every `LAZY` array must have been allocated. They must not be otherwise
+    /// accessed for `'a`.
+    unsafe fn row_mut<'a>(pointers: &[ptr::NonNull<u8>], index: usize) -> Self::Mut<'a>
+    where Self: 'a;
+}
+
+/// How the elements of one of a table's arrays are stored.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Array {
+    /// Elements of `size` bytes, aligned to `align`.
+    Bytes {
+        /// The size of each element.
+        size: usize,
+        /// The alignment of each element.
+        align: usize,
+    },
+    /// Packed bits, one per row, stored in `usize` words.
+    Bits,
+}
+
+impl Array {
+    /// Describe an array of `F`s.
+    pub const fn of<F>() -> Self {
+        Array::Bytes { size: mem::size_of::<F>(), align: mem::align_of::<F>() }
+    }
+
+    /// Get the alignment of the array.
+    fn align(self) -> usize {
+        match self {
+            Array::Bytes { align, .. } => align,
+            Array::Bits => mem::align_of::<usize>(),
+        }
+    }
+
+    /// Get the size of an array of `capacity` elements, or `None` on overflow.
+    fn len(self, capacity: usize) -> Option<usize> {
+        match self {
+            Array::Bytes { size, .. } => usize::checked_mul(capacity, size),
+            Array::Bits => Some(bits::words(capacity) * mem::size_of::<usize>()),
+        }
+    }
+
+    /// Copy `count` elements of the array at `data` from index `src` to index `dst`, which may
+    /// overlap.
+    unsafe fn copy(self, data: *mut u8, src: usize, dst: usize, count: usize) {
+        match self {
+            Array::Bytes { size, .. } => {
+                ptr::copy(data.add(src * size), data.add(dst * size), count * size)
+            }
+            Array::Bits => bits::copy(data, src, dst, count),
+        }
+    }
+
+    /// Reorder the array at `data` so that element `i` is moved from element `permutation[i]`.
+    ///
+    /// Each cycle of the permutation is followed in turn, holding its first element in `temp`,
+    /// which must have space for one element. `pending` tracks the elements yet to be moved, and
+    /// must be as long as `permutation`.
+    unsafe fn permute(
+        self, data: *mut u8, permutation: &[usize], temp: *mut u8, pending: &mut [bool]
+    ) {
+        pending.fill(true);
+        for start in 0..permutation.len() {
+            if !pending[start] || permutation[start] == start {
+                continue;
+            }
+
+            self.save(data, start, temp);
+            let mut index = start;
+            loop {
+                pending[index] = false;
+                let next = permutation[index];
+                if next == start {
+                    self.restore(data, index, temp);
+                    break;
+                }
+                self.copy(data, next, index, 1);
+                index = next;
+            }
+        }
+    }
+
+    /// Copy element `index` of the array at `data` into `temp`.
+    unsafe fn save(self, data: *mut u8, index: usize, temp: *mut u8) {
+        match self {
+            Array::Bytes { size, .. } => {
+                ptr::copy_nonoverlapping(data.add(index * size), temp, size)
+            }
+            Array::Bits => *temp = bits::get(data, index) as u8,
+        }
+    }
+
+    /// Copy the element in `temp` into element `index` of the array at `data`.
+    unsafe fn restore(self, data: *mut u8, index: usize, temp: *const u8) {
+        match self {
+            Array::Bytes { size, .. } => {
+                ptr::copy_nonoverlapping(temp, data.add(index * size), size)
+            }
+            Array::Bits => bits::set(data, index, *temp != 0),
+        }
+    }
+}
+
+/// How a field is stored in a table, in terms of the indices of its arrays.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Storage {
+    /// An array of the field's own type.
+    Plain {
+        /// The index of the field's array.
+        array: usize,
+    },
+    /// An `Option<F>` field, stored as an array of `F` and a bitset recording which rows hold a
+    /// value.
+    Nullable {
+        /// The index of the field's values.
+        values: usize,
+        /// The index of the field's validity bitset.
+        validity: usize,
+    },
+    /// A `bool` field, stored as a bitset.
+    Bits {
+        /// The index of the field's bitset.
+        array: usize,
+    },
+    /// A `#[dioptre(flatten)]` field, whose type's own arrays are stored consecutively.
+    Flatten {
+        /// The index of the first of the field's arrays.
+        start: usize,
+        /// The number of arrays.
+        len: usize,
+    },
+}
+
+impl Storage {
+    /// Adjust the array indices of a field whose arrays are embedded in a larger table, starting
+    /// at index `offset`.
+    const fn nest(self, offset: usize) -> Self {
+        match self {
+            Storage::Plain { array } => Storage::Plain { array: offset + array },
+            Storage::Nullable { values, validity } => {
+                Storage::Nullable { values: offset + values, validity: offset + validity }
+            }
+            Storage::Bits { array } => Storage::Bits { array: offset + array },
+            Storage::Flatten { start, len } => Storage::Flatten { start: offset + start, len },
+        }
+    }
+
+    /// Get the indices of the field's arrays.
+    fn arrays(self) -> impl Iterator<Item = usize> {
+        let (first, second) = match self {
+            Storage::Plain { array } | Storage::Bits { array } => (array..array + 1, 0..0),
+            Storage::Nullable { values, validity } => (values..values + 1, validity..validity + 1),
+            Storage::Flatten { start, len } => (start..start + len, 0..0),
+        };
+        first.chain(second)
+    }
+}
+
+/// Check whether array `array` of `T` at `pointers` has been allocated, which is only ever not the
+/// case for `LAZY` arrays.
+fn is_allocated<T: Columns>(pointers: &[ptr::NonNull<u8>], array: usize) -> bool {
+    !T::LAZY[array] || pointers[array] != T::dangling().borrow()[array]
+}
+
+/// Get the index of the array of field `index` of `T`.
+///
+/// # Panics
+///
+/// Panics if the field is not stored as a plain array of its own type.
+fn plain_array<T: Columns>(index: usize) -> usize {
+    match T::STORAGE[index] {
+        Storage::Plain { array } => array,
+        _ => panic!("field is not stored as a plain array"),
+    }
+}
+
+/// Get the indices of the values and validity bitset of field `index` of `T`.
+///
+/// # Panics
+///
+/// Panics if the field is not nullable.
+fn nullable_arrays<T: Columns>(index: usize) -> (usize, usize) {
+    match T::STORAGE[index] {
+        Storage::Nullable { values, validity } => (values, validity),
+        _ => panic!("field is not nullable"),
+    }
+}
+
+/// Get pointers to the values and validity bitset of nullable field `index` of `T`. The validity
+/// pointer is null if the field is `LAZY` and has not been allocated.
+///
+/// # Panics
+///
+/// Panics if the field is not nullable.
+fn nullable_pointers<T: Columns, F>(pointers: &[ptr::NonNull<u8>], index: usize)
+    -> (*mut F, *mut u8)
+{
+    let (values, validity) = nullable_arrays::<T>(index);
+    let values = pointers[values].as_ptr() as *mut F;
+    match is_allocated::<T>(pointers, validity) {
+        true => (values, pointers[validity].as_ptr()),
+        false => (values, ptr::null_mut()),
+    }
+}
+
+/// Get the index of the bitset of field `index` of `T`.
+///
+/// # Panics
+///
+/// Panics if the field is not stored as bits.
+fn bit_array<T: Columns>(index: usize) -> usize {
+    match T::STORAGE[index] {
+        Storage::Bits { array } => array,
+        _ => panic!("field is not stored as bits"),
+    }
+}
+
+/// Get pointers to the arrays of a flattened field of `T`, laid out for the field's type.
+///
+/// # Panics
+///
+/// Panics if the field is not flattened.
+fn group_pointers<T: Columns, F: Columns>(pointers: &[ptr::NonNull<u8>], field: Field<T, F>)
+    -> F::Pointers
+{
+    let range = match T::STORAGE[field.index()] {
+        Storage::Flatten { start, len } => start..start + len,
+        _ => panic!("field is not flattened"),
+    };
+
+    let mut nested = F::dangling();
+    nested.borrow_mut().copy_from_slice(&pointers[range]);
+    nested
+}
+
+/// Helpers for code generated by `#[derive(Columns)]`.
+#[doc(hidden)]
+pub mod __private {
+    use core::ptr;
+    use crate::Storage;
+
+    pub use crate::bits::{get, set};
+    pub use dioptre::__private::concat;
+
+    /// Get a pointer to the validity bitset of a `#[soak(lazy, nullable)]` field, or null if it
+    /// has not been allocated.
+    pub fn bitset(pointer: ptr::NonNull<u8>) -> *mut u8 {
+        match pointer == ptr::NonNull::<usize>::dangling().cast() {
+            true => ptr::null_mut(),
+            false => pointer.as_ptr(),
+        }
+    }
+
+    /// Read bit `index` of a `#[soak(lazy, nullable)]` field's validity bitset, which is unset if
+    /// the bitset is null.
+    pub unsafe fn get_lazy(data: *mut u8, index: usize) -> bool {
+        !data.is_null() && get(data, index)
+    }
+
+    /// Clear bit `index` of a `#[soak(lazy, nullable)]` field's validity bitset, unless it is null.
+    pub unsafe fn clear_lazy(data: *mut u8, index: usize) {
+        if !data.is_null() {
+            set(data, index, false)
+        }
+    }
+
+    /// Adjust the `STORAGE` of a flattened field's type, for arrays that start at index `start`.
+    pub const fn nest<const N: usize>(storage: &[Storage], start: usize) -> [Storage; N] {
+        let mut array = [Storage::Plain { array: 0 }; N];
+        let mut index = 0;
+        while index < N {
+            array[index] = storage[index].nest(start);
+            index += 1;
+        }
+        array
+    }
 }
 
 /// A raw allocation containing parallel arrays of `T`'s fields.
@@ -79,20 +612,24 @@ pub unsafe trait Columns: Fields {
 /// Much like `std`'s `RawVec`, `RawTable` manages an allocation for a collection, but without
 /// managing the initialization or dropping of its contents. `RawTable` does not deal directly with
 /// elements of type `T`, but with multiple adjacent arrays of `T`'s fields, shared in a single
-/// allocation.
-pub struct RawTable<T: Columns> {
+/// allocation. Arrays of `#[soak(cold)]` fields are instead shared in a second allocation, so
+/// frequently used arrays can be packed together, and arrays of `#[soak(lazy)]` fields are each
+/// allocated on their own, only once they are needed.
+///
+/// The allocation comes from `A`, which defaults to the [`Global`] allocator.
+pub struct RawTable<T: Columns, A: Allocator = Global> {
     pointers: T::Pointers,
     capacity: usize,
+    alloc: A,
     _marker: PhantomData<T>,
 }
 
-impl<T: Columns> Default for RawTable<T> {
+unsafe impl<T: Columns + Send, A: Allocator + Send> Send for RawTable<T, A> {}
+unsafe impl<T: Columns + Sync, A: Allocator + Sync> Sync for RawTable<T, A> {}
+
+impl<T: Columns, A: Allocator + Default> Default for RawTable<T, A> {
     /// Create a `RawTable` without allocating.
-    fn default() -> Self {
-        let pointers = T::dangling();
-        let capacity = if mem::size_of::<T>() == 0 { usize::MAX } else { 0 };
-        RawTable { pointers, capacity, _marker: PhantomData }
-    }
+    fn default() -> Self { Self::new_in(A::default()) }
 }
 
 impl<T: Columns> RawTable<T> {
@@ -105,46 +642,351 @@ impl<T: Columns> RawTable<T> {
     /// # Aborts
     ///
     /// Aborts on OOM.
-    pub fn with_capacity(capacity: usize) -> Self {
-        unsafe {
-            let align = T::ALIGNS.iter().cloned().max().unwrap_or(1);
-            let mask = align - 1;
-            let size = T::SIZES.iter().try_fold(0, move |sum, &size| {
-                let array_size = usize::checked_mul(capacity, size)?;
-                let aligned_size = usize::checked_add(array_size, mask)? & !mask;
-                Some(usize::checked_add(sum, aligned_size)?)
-            }).expect("capacity overflow");
+    pub fn with_capacity(capacity: usize) -> Self { Self::with_capacity_in(capacity, Global) }
 
-            let layout = Layout::from_size_align_unchecked(size, align);
-            let data = if size == 0 { align as *mut u8 } else { alloc(layout) };
-            if data == ptr::null_mut() {
-                handle_alloc_error(layout);
-            }
+    /// Create a `RawTable` with enough space for `capacity` elements of each field type, returning
+    /// an error if the allocation fails.
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_in(capacity, Global)
+    }
+}
+
+impl<T: Columns, A: Allocator> RawTable<T, A> {
+    /// Create a `RawTable` in `alloc` without allocating.
+    pub fn new_in(alloc: A) -> Self {
+        let pointers = T::dangling();
+        let capacity = if mem::size_of::<T>() == 0 { usize::MAX } else { 0 };
+        RawTable { pointers, capacity, alloc, _marker: PhantomData }
+    }
+
+    /// Create a `RawTable` in `alloc` with enough space for `capacity` elements of each field
+    /// type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        handle_reserve(Self::try_with_capacity_in(capacity, alloc))
+    }
 
+    /// Create a `RawTable` in `alloc` with enough space for `capacity` elements of each field
+    /// type, returning an error if the allocation fails.
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        let capacity = Self::pad(capacity).ok_or(TryReserveError::CapacityOverflow)?;
+        let pointers = Self::allocate(&alloc, capacity)?;
+        let capacity = if mem::size_of::<T>() == 0 { usize::MAX } else { capacity };
+        Ok(RawTable { pointers, capacity, alloc, _marker: PhantomData })
+    }
+
+    /// Get a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A { &self.alloc }
+
+    /// Allocate field arrays for `capacity` elements, leaving `LAZY` arrays unallocated.
+    fn allocate(alloc: &A, capacity: usize) -> Result<T::Pointers, TryReserveError> {
+        unsafe {
             let mut pointers = T::dangling();
-            let mut offset = 0;
-            let dst = pointers.borrow_mut().iter_mut();
-            for (pointer, size) in Iterator::zip(dst, T::SIZES.iter()) {
-                *pointer = ptr::NonNull::new_unchecked(data.add(offset));
-                offset += (capacity * size + mask) & !mask;
+            Self::allocate_group(alloc, capacity, false, &mut pointers)?;
+            if let Err(error) = Self::allocate_group(alloc, capacity, true, &mut pointers) {
+                Self::deallocate_group(alloc, &pointers, capacity, false);
+                return Err(error);
             }
 
-            let capacity = if mem::size_of::<T>() == 0 { usize::MAX } else { capacity };
+            // Bitsets are accessed a word at a time, so must start out initialized.
+            for (index, (pointer, &array)) in pointers.borrow().iter().zip(T::ARRAYS).enumerate() {
+                if array == Array::Bits && !T::LAZY[index] {
+                    ptr::write_bytes(pointer.as_ptr(), 0, array.len(capacity).unwrap_unchecked());
+                }
+            }
+
+            Ok(pointers)
+        }
+    }
+
+    /// Allocate the hot or `cold` field arrays for `capacity` elements, and point `pointers` at
+    /// them.
+    unsafe fn allocate_group(alloc: &A, capacity: usize, cold: bool, pointers: &mut T::Pointers)
+        -> Result<(), TryReserveError>
+    {
+        let layout = Self::layout(capacity, cold).ok_or(TryReserveError::CapacityOverflow)?;
+        let data = if layout.size() == 0 {
+            layout.align() as *mut u8
+        } else {
+            alloc.alloc(layout)
+        };
+        if data.is_null() {
+            return Err(TryReserveError::AllocError { layout });
+        }
+
+        Self::place_group(pointers, data, capacity, cold);
+        Ok(())
+    }
+
+    /// Point `pointers` at the hot or `cold` arrays within `data`, laid out for `capacity`
+    /// elements.
+    unsafe fn place_group(pointers: &mut T::Pointers, data: *mut u8, capacity: usize, cold: bool) {
+        let align = Self::layout(capacity, cold).unwrap_unchecked().align();
+        let lens = Self::arrays(cold).map(|array| array.len(capacity).unwrap_unchecked());
+        let pointers = pointers.borrow_mut().iter_mut().enumerate()
+            .filter(|&(index, _)| Self::in_group(index, cold))
+            .map(|(_, pointer)| pointer);
+        place_arrays(data, lens, align, pointers);
+    }
+
+    /// Allocate the `LAZY` array `array` for `capacity` elements, and point `pointers` at it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocator returns the array's dangling pointer, which would be mistaken for
+    /// an unallocated array.
+    unsafe fn allocate_lazy(alloc: &A, array: usize, capacity: usize, pointers: &mut T::Pointers)
+        -> Result<(), TryReserveError>
+    {
+        let layout = Self::lazy_layout(array, capacity).ok_or(TryReserveError::CapacityOverflow)?;
+        if layout.size() == 0 {
+            return Ok(());
+        }
+
+        let data = alloc.alloc(layout);
+        if data.is_null() {
+            return Err(TryReserveError::AllocError { layout });
+        }
+        assert!(data != T::dangling().borrow()[array].as_ptr(), "allocation at a dangling address");
+
+        if T::ARRAYS[array] == Array::Bits {
+            ptr::write_bytes(data, 0, layout.size());
+        }
+        pointers.borrow_mut()[array] = ptr::NonNull::new_unchecked(data);
+        Ok(())
+    }
+
+    /// Allocate the `LAZY` array `array` for the table's capacity, if it has not been already.
+    fn try_allocate_array(&mut self, array: usize) -> Result<(), TryReserveError> {
+        if is_allocated::<T>(self.pointers.borrow(), array) {
+            return Ok(());
+        }
+
+        unsafe { Self::allocate_lazy(&self.alloc, array, self.capacity, &mut self.pointers) }
+    }
+
+    /// Free the field arrays without dropping their elements.
+    unsafe fn deallocate(&mut self) {
+        Self::deallocate_arrays(&self.alloc, &self.pointers, self.capacity)
+    }
 
-            RawTable { pointers, capacity, _marker: PhantomData }
+    /// Free all of the field arrays of `capacity` elements at `pointers`.
+    unsafe fn deallocate_arrays(alloc: &A, pointers: &T::Pointers, capacity: usize) {
+        Self::deallocate_group(alloc, pointers, capacity, false);
+        Self::deallocate_group(alloc, pointers, capacity, true);
+        for (array, data) in pointers.borrow().iter().enumerate() {
+            if T::LAZY[array] && is_allocated::<T>(pointers.borrow(), array) {
+                alloc.dealloc(data.as_ptr(), Self::lazy_layout(array, capacity).unwrap_unchecked());
+            }
         }
     }
 
-    /// Get a pointer to a field array.
+    /// Free the hot or `cold` field arrays of `capacity` elements at `pointers`.
+    unsafe fn deallocate_group(alloc: &A, pointers: &T::Pointers, capacity: usize, cold: bool) {
+        let layout = Self::layout(capacity, cold).unwrap_unchecked();
+        let mut pointers = pointers.borrow().iter().enumerate();
+        match pointers.find(|&(index, _)| Self::in_group(index, cold)) {
+            Some((_, data)) if layout.size() > 0 => alloc.dealloc(data.as_ptr(), layout),
+            _ => {}
+        }
+    }
+
+    /// Round `capacity` up so each field array ends on a `T::ALIGN` boundary, or `None` on
+    /// overflow.
+    fn pad(capacity: usize) -> Option<usize> {
+        let lanes = T::ARRAYS.iter()
+            .filter_map(|&array| match array {
+                Array::Bytes { size, .. } if size > 0 => Some(size),
+                _ => None,
+            })
+            .map(|size| T::ALIGN / cmp::min(T::ALIGN, size & size.wrapping_neg()))
+            .max().unwrap_or(1);
+        let mask = lanes - 1;
+        Some(usize::checked_add(capacity, mask)? & !mask)
+    }
+
+    /// Compute the layout of the hot or `cold` allocation for `capacity` elements, or `None` on
+    /// overflow.
+    fn layout(capacity: usize, cold: bool) -> Option<Layout> {
+        let align = Self::arrays(cold).map(Array::align).fold(T::ALIGN, cmp::max);
+        arrays_layout(Self::arrays(cold).map(|array| array.len(capacity)), align)
+    }
+
+    /// Compute the layout of the `LAZY` array `array` for `capacity` elements, or `None` on
+    /// overflow.
+    fn lazy_layout(array: usize, capacity: usize) -> Option<Layout> {
+        let array = T::ARRAYS[array];
+        Layout::from_size_align(array.len(capacity)?, cmp::max(array.align(), T::ALIGN)).ok()
+    }
+
+    /// Get the hot or `cold` arrays.
+    fn arrays(cold: bool) -> impl DoubleEndedIterator<Item = Array> {
+        T::ARRAYS.iter().enumerate()
+            .filter(move |&(index, _)| Self::in_group(index, cold))
+            .map(|(_, &array)| array)
+    }
+
+    /// Check whether array `index` belongs in the hot or `cold` allocation.
+    fn in_group(index: usize, cold: bool) -> bool { T::COLD[index] == cold && !T::LAZY[index] }
+
+    /// Get a pointer to a field array. A `#[soak(lazy)]` field's array is allocated if it has not
+    /// been already, with its elements uninitialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
     pub fn ptr<F>(&mut self, field: Field<T, F>) -> *mut F {
-        self.pointers.borrow()[field.index()].as_ptr() as *mut F
+        let array = plain_array::<T>(field.index());
+        handle_reserve(self.try_allocate_array(array));
+        self.pointers.borrow()[array].as_ptr() as *mut F
+    }
+
+    /// Check whether a field's arrays have been allocated, which is only ever not the case for
+    /// `#[soak(lazy)]` fields.
+    pub fn is_allocated<F>(&self, field: Field<T, F>) -> bool {
+        let pointers = self.pointers.borrow();
+        T::STORAGE[field.index()].arrays()
+            .all(|array| T::ARRAYS[array].len(1) == Some(0) || is_allocated::<T>(pointers, array))
+    }
+
+    /// Allocate the arrays of a `#[soak(lazy)]` field, if they have not been already. The rows of
+    /// a `nullable` field start out empty, and those of any other field uninitialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn allocate_field<F>(&mut self, field: Field<T, F>) {
+        handle_reserve(self.try_allocate_arrays(field.index()))
+    }
+
+    /// Allocate the arrays of a `#[soak(lazy)]` field, if they have not been already, returning an
+    /// error if the allocation fails.
+    pub fn try_allocate_field<F>(&mut self, field: Field<T, F>) -> Result<(), TryReserveError> {
+        self.try_allocate_arrays(field.index())
+    }
+
+    /// Allocate the arrays of field `index`, if they have not been already.
+    fn try_allocate_arrays(&mut self, index: usize) -> Result<(), TryReserveError> {
+        T::STORAGE[index].arrays().try_for_each(|array| self.try_allocate_array(array))
+    }
+
+    /// Allocate every `LAZY` array that has not been already, as mutable row views require.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub(crate) fn allocate_all(&mut self) {
+        for array in 0..T::ARRAYS.len() {
+            handle_reserve(self.try_allocate_array(array));
+        }
     }
 
     /// Get the capacity of the allocation.
     pub fn capacity(&self) -> usize { self.capacity }
 
+    /// Move `row` into the field arrays at `index`, first allocating any `#[soak(lazy)]` arrays
+    /// that it needs.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than the table's capacity. Any values already at `index` are
+    /// overwritten without being dropped.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub unsafe fn write(&mut self, index: usize, row: T) {
+        for array in 0..T::ARRAYS.len() {
+            if !is_allocated::<T>(self.pointers.borrow(), array) && row.needs(array) {
+                handle_reserve(self.try_allocate_array(array));
+            }
+        }
+        row.write(self.pointers.borrow(), index)
+    }
+
+    /// Move the fields at `index` out of the field arrays, reassembling them into a `T`.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than the table's capacity, and its fields must be initialized. They
+    /// are left logically uninitialized.
+    pub unsafe fn read(&mut self, index: usize) -> T {
+        T::read(self.pointers.borrow(), index)
+    }
+
+    /// Replace the row at `index` with `row`, returning the old one.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than the table's capacity, and its fields must be initialized.
+    pub unsafe fn replace(&mut self, index: usize, row: T) -> T {
+        let old = self.read(index);
+        self.write(index, row);
+        old
+    }
+
+    /// Copy `count` elements of each field array from index `src` to index `dst`.
+    ///
+    /// The source and destination may overlap. Like [`ptr::copy`], this leaves the source elements
+    /// initialized and overwrites the destination elements without dropping them.
+    ///
+    /// # Safety
+    ///
+    /// Both `src + count` and `dst + count` must be no greater than the table's capacity.
+    pub unsafe fn copy(&mut self, src: usize, dst: usize, count: usize) {
+        let pointers = self.pointers.borrow();
+        for (index, (pointer, array)) in pointers.iter().zip(T::ARRAYS).enumerate() {
+            if is_allocated::<T>(pointers, index) {
+                array.copy(pointer.as_ptr(), src, dst, count);
+            }
+        }
+    }
+
+    /// Reorder the first `permutation.len()` elements of each field array so that element `i` is
+    /// moved from element `permutation[i]`, one array at a time.
+    ///
+    /// # Safety
+    ///
+    /// `permutation` must be a permutation of `0..permutation.len()`, and no longer than the
+    /// table's capacity.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub(crate) unsafe fn permute(&mut self, permutation: &[usize]) {
+        // Hold one element of the widest array, or one bit.
+        let size = T::ARRAYS.iter().map(|array| array.len(1).unwrap_unchecked()).fold(1, cmp::max);
+        let mut temp = vec![0u8; size];
+        let mut pending = vec![true; permutation.len()];
+
+        let pointers = self.pointers.borrow();
+        for (index, (pointer, array)) in pointers.iter().zip(T::ARRAYS).enumerate() {
+            if is_allocated::<T>(pointers, index) && array.len(1) != Some(0) {
+                array.permute(pointer.as_ptr(), permutation, temp.as_mut_ptr(), &mut pending);
+            }
+        }
+    }
+
     /// Ensure that the table contains enough space for `used + extra` elements.
     ///
+    /// The table's allocations are resized with [`Allocator::realloc`], so they can grow in place
+    /// when the allocator allows it.
+    ///
     /// # Panics
     ///
     /// Panics if the requested capacity exceeds [`usize::MAX`] bytes.
@@ -153,37 +995,316 @@ impl<T: Columns> RawTable<T> {
     ///
     /// Aborts on OOM.
     pub fn reserve_exact(&mut self, used: usize, extra: usize) {
+        handle_reserve(self.try_reserve_exact(used, extra))
+    }
+
+    /// Ensure that the table contains enough space for `used + extra` elements, returning an error
+    /// if the allocation fails.
+    pub fn try_reserve_exact(&mut self, used: usize, extra: usize) -> Result<(), TryReserveError> {
+        if used <= self.capacity && self.capacity - used >= extra {
+            return Ok(());
+        }
+
+        let capacity = usize::checked_add(used, extra).ok_or(TryReserveError::CapacityOverflow)?;
+        self.relayout(cmp::min(used, self.capacity), capacity)
+    }
+
+    /// Ensure that the table contains enough space for `used + extra` elements, growing it
+    /// geometrically to amortize the cost of repeated calls.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn reserve(&mut self, used: usize, extra: usize) {
+        handle_reserve(self.try_reserve(used, extra))
+    }
+
+    /// Ensure that the table contains enough space for `used + extra` elements, growing it
+    /// geometrically and returning an error if the allocation fails.
+    pub fn try_reserve(&mut self, used: usize, extra: usize) -> Result<(), TryReserveError> {
+        if used <= self.capacity && self.capacity - used >= extra {
+            return Ok(());
+        }
+
+        let required = usize::checked_add(used, extra).ok_or(TryReserveError::CapacityOverflow)?;
+        let capacity = cmp::max(cmp::max(self.capacity.saturating_mul(2), required), 4);
+        self.relayout(cmp::min(used, self.capacity), capacity)
+    }
+
+    /// Shrink the table's capacity as close as possible to `max(used, capacity)`.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn shrink_to(&mut self, used: usize, capacity: usize) {
+        let capacity = match Self::pad(cmp::max(used, capacity)) {
+            Some(capacity) if mem::size_of::<T>() > 0 && capacity < self.capacity => capacity,
+            _ => return,
+        };
+
+        handle_reserve(self.relayout(used, capacity))
+    }
+
+    /// Shrink the table's capacity as close as possible to `used`.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn shrink_to_fit(&mut self, used: usize) { self.shrink_to(used, used) }
+
+    /// Move the first `used` elements of each field array into a new allocation of `capacity`.
+    /// `LAZY` arrays that have not been allocated stay that way.
+    ///
+    /// When the table grows, its allocations are instead resized, with [`RawTable::grow`].
+    fn relayout(&mut self, used: usize, capacity: usize) -> Result<(), TryReserveError> {
         unsafe {
-            if self.capacity - used >= extra {
-                return;
+            let capacity = Self::pad(capacity).ok_or(TryReserveError::CapacityOverflow)?;
+            if capacity > self.capacity {
+                return self.grow(used, capacity);
             }
 
-            let capacity = usize::checked_add(used, extra).expect("capacity overflow");
-            let table = Self::with_capacity(capacity);
+            let mut pointers = Self::allocate(&self.alloc, capacity)?;
+            for array in 0..T::ARRAYS.len() {
+                if !T::LAZY[array] || !is_allocated::<T>(self.pointers.borrow(), array) {
+                    continue;
+                }
+                let result = Self::allocate_lazy(&self.alloc, array, capacity, &mut pointers);
+                if let Err(error) = result {
+                    Self::deallocate_arrays(&self.alloc, &pointers, capacity);
+                    return Err(error);
+                }
+            }
 
             let src = self.pointers.borrow().iter();
-            let dst = table.pointers.borrow().iter();
-            for ((src, dst), size) in Iterator::zip(Iterator::zip(src, dst), T::SIZES.iter()) {
-                ptr::copy_nonoverlapping(src.as_ptr(), dst.as_ptr(), self.capacity * size);
+            let dst = pointers.borrow().iter();
+            for (index, ((src, dst), array)) in src.zip(dst).zip(T::ARRAYS).enumerate() {
+                if is_allocated::<T>(self.pointers.borrow(), index) {
+                    let len = array.len(used).unwrap_unchecked();
+                    ptr::copy_nonoverlapping(src.as_ptr(), dst.as_ptr(), len);
+                }
+            }
+
+            self.deallocate();
+            self.pointers = pointers;
+            self.capacity = capacity;
+            Ok(())
+        }
+    }
+
+    /// Grow the table to `capacity` elements, keeping the first `used` elements of each field
+    /// array.
+    ///
+    /// Each allocation is resized with [`Allocator::realloc`], which can often extend it in place
+    /// rather than copying it, and its arrays are then moved back to front into their new offsets.
+    /// If any allocation fails, those already resized are shrunk back.
+    unsafe fn grow(&mut self, used: usize, capacity: usize) -> Result<(), TryReserveError> {
+        let blocks = 2 + T::ARRAYS.len();
+        if (0..blocks).any(|block| matches!(self.block(block, capacity), Some((_, _, None)))) {
+            return Err(TryReserveError::CapacityOverflow);
+        }
+
+        for block in 0..blocks {
+            if let Some((array, layout, Some(new_layout))) = self.block(block, capacity) {
+                if let Err(error) = self.resize(array, layout, new_layout) {
+                    self.shrink_blocks(block, capacity);
+                    return Err(error);
+                }
             }
+        }
 
-            let _ = mem::replace(self, table);
+        self.move_group(used, capacity, false);
+        self.move_group(used, capacity, true);
+        let pointers = self.pointers.borrow();
+        for (array, pointer) in pointers.iter().enumerate() {
+            let is_bits = T::ARRAYS[array] == Array::Bits;
+            if is_bits && T::LAZY[array] && is_allocated::<T>(pointers, array) {
+                let len = Array::Bits.len(used).unwrap_unchecked();
+                let new_len = Array::Bits.len(capacity).unwrap_unchecked();
+                ptr::write_bytes(pointer.as_ptr().add(len), 0, new_len - len);
+            }
         }
+
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    /// Shrink the first `blocks` allocations back from `capacity` elements to the table's current
+    /// capacity, after a failed [`RawTable::grow`].
+    ///
+    /// # Aborts
+    ///
+    /// Aborts if an allocation cannot be shrunk.
+    unsafe fn shrink_blocks(&mut self, blocks: usize, capacity: usize) {
+        for block in 0..blocks {
+            if let Some((array, layout, Some(new_layout))) = self.block(block, capacity) {
+                handle_reserve(self.resize(array, new_layout, layout));
+            }
+        }
+
+        let first = |cold| (0..T::ARRAYS.len()).find(|&array| Self::in_group(array, cold));
+        for cold in [false, true] {
+            if let Some(array) = first(cold) {
+                let data = self.pointers.borrow()[array].as_ptr();
+                Self::place_group(&mut self.pointers, data, self.capacity, cold);
+            }
+        }
+    }
+
+    /// Describe one of the table's allocations: the hot one for `block` 0, the cold one for 1, and
+    /// each `LAZY` array after that. Returns the index of its first array, and its layouts at the
+    /// current capacity and at `capacity`, or `None` if it has no arrays or is not allocated.
+    fn block(&self, block: usize, capacity: usize) -> Option<(usize, Layout, Option<Layout>)> {
+        let (array, layout, new_layout) = match block {
+            0 | 1 => {
+                let cold = block == 1;
+                let array = (0..T::ARRAYS.len()).find(|&array| Self::in_group(array, cold))?;
+                (array, Self::layout(self.capacity, cold), Self::layout(capacity, cold))
+            }
+            _ => {
+                let array = block - 2;
+                if !T::LAZY[array] || !is_allocated::<T>(self.pointers.borrow(), array) {
+                    return None;
+                }
+                (array, Self::lazy_layout(array, self.capacity), Self::lazy_layout(array, capacity))
+            }
+        };
+        Some((array, unsafe { layout.unwrap_unchecked() }, new_layout))
+    }
+
+    /// Resize the allocation holding array `array`, which starts it, from `layout` to
+    /// `new_layout`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocator returns a `LAZY` array's dangling pointer, which would be mistaken
+    /// for an unallocated array.
+    unsafe fn resize(&mut self, array: usize, layout: Layout, new_layout: Layout)
+        -> Result<(), TryReserveError>
+    {
+        let data = self.pointers.borrow()[array].as_ptr();
+        let data = match (layout.size(), new_layout.size()) {
+            (0, 0) => return Ok(()),
+            (0, _) => self.alloc.alloc(new_layout),
+            (_, 0) => {
+                self.alloc.dealloc(data, layout);
+                new_layout.align() as *mut u8
+            }
+            (_, size) => self.alloc.realloc(data, layout, size),
+        };
+        if data.is_null() {
+            return Err(TryReserveError::AllocError { layout: new_layout });
+        }
+        if T::LAZY[array] {
+            let dangling = T::dangling().borrow()[array].as_ptr();
+            assert!(data != dangling, "allocation at a dangling address");
+        }
+
+        self.pointers.borrow_mut()[array] = ptr::NonNull::new_unchecked(data);
+        Ok(())
+    }
+
+    /// Move the first `used` elements of each hot or `cold` array from its offset at the table's
+    /// current capacity to its offset at `capacity`, once the allocation has grown.
+    ///
+    /// Arrays only move towards the end of the allocation, so moving them back to front never
+    /// overwrites an array before it has moved. Bitsets are zeroed past their first `used` bits.
+    unsafe fn move_group(&mut self, used: usize, capacity: usize, cold: bool) {
+        let array = match (0..T::ARRAYS.len()).find(|&array| Self::in_group(array, cold)) {
+            Some(array) => array,
+            None => return,
+        };
+        let data = self.pointers.borrow()[array].as_ptr();
+        let layout = Self::layout(self.capacity, cold).unwrap_unchecked();
+        let new_layout = Self::layout(capacity, cold).unwrap_unchecked();
+
+        let mask = layout.align() - 1;
+        let (mut offset, mut new_offset) = (layout.size(), new_layout.size());
+        for array in Self::arrays(cold).rev() {
+            let len = array.len(used).unwrap_unchecked();
+            let new_len = array.len(capacity).unwrap_unchecked();
+            offset -= (array.len(self.capacity).unwrap_unchecked() + mask) & !mask;
+            new_offset -= (new_len + mask) & !mask;
+            ptr::copy(data.add(offset), data.add(new_offset), len);
+            if array == Array::Bits {
+                ptr::write_bytes(data.add(new_offset + len), 0, new_len - len);
+            }
+        }
+
+        Self::place_group(&mut self.pointers, data, capacity, cold);
     }
 }
 
-impl<T: Columns> Drop for RawTable<T> {
+impl<T: Columns, A: Allocator> Drop for RawTable<T, A> {
     /// Free the underlying buffer but do not drop the arrays' elements.
     fn drop(&mut self) {
-        unsafe {
-            let align = *T::ALIGNS.iter().max().unwrap_or(&1);
-            let mask = align - 1;
+        unsafe { self.deallocate() }
+    }
+}
 
-            let capacity = self.capacity;
-            let size = T::SIZES.iter().map(move |&size| (capacity * size + mask) & !mask).sum();
+/// The error type for `try_reserve` methods.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TryReserveError {
+    /// The requested capacity exceeds [`usize::MAX`] bytes.
+    CapacityOverflow,
+    /// The allocator returned an error.
+    AllocError {
+        /// The layout of the failed allocation.
+        layout: Layout,
+    },
+}
 
-            let layout = Layout::from_size_align_unchecked(size, align);
-            if size > 0 { dealloc(self.pointers.borrow()[0].as_ptr(), layout); }
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")?;
+        match *self {
+            TryReserveError::CapacityOverflow => f.write_str(" because the capacity overflowed"),
+            TryReserveError::AllocError { .. } => f.write_str(" because the allocator failed"),
         }
     }
 }
+
+impl core::error::Error for TryReserveError {}
+
+/// Compute the layout of adjacent arrays of `lens` bytes, each starting on an `align` boundary,
+/// or `None` on overflow.
+fn arrays_layout<I>(lens: I, align: usize) -> Option<Layout>
+    where I: Iterator<Item = Option<usize>>
+{
+    let mask = align - 1;
+    let size = lens.into_iter().try_fold(0, move |sum, len| {
+        let aligned_len = usize::checked_add(len?, mask)? & !mask;
+        usize::checked_add(sum, aligned_len)
+    })?;
+
+    Layout::from_size_align(size, align).ok()
+}
+
+/// Point `pointers` at adjacent arrays within `data`, as laid out by `arrays_layout`.
+///
+/// # Safety
+///
+/// `data` must point to an allocation with the layout computed by `arrays_layout` for the same
+/// `lens` and `align`.
+unsafe fn place_arrays<'a, I, P>(data: *mut u8, lens: I, align: usize, pointers: P)
+    where I: Iterator<Item = usize>, P: IntoIterator<Item = &'a mut ptr::NonNull<u8>>
+{
+    let mask = align - 1;
+    let mut offset = 0;
+    for (pointer, len) in Iterator::zip(pointers.into_iter(), lens) {
+        *pointer = ptr::NonNull::new_unchecked(data.add(offset));
+        offset += (len + mask) & !mask;
+    }
+}
+
+/// Unwrap the result of a `try_` method, panicking or aborting on failure.
+fn handle_reserve<R>(result: Result<R, TryReserveError>) -> R {
+    match result {
+        Ok(result) => result,
+        Err(TryReserveError::CapacityOverflow) => panic!("capacity overflow"),
+        Err(TryReserveError::AllocError { layout }) => handle_alloc_error(layout),
+    }
+}
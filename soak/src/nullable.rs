@@ -0,0 +1,236 @@
+use core::{fmt, ptr};
+use core::marker::PhantomData;
+
+use crate::bits;
+
+/// A mutable reference to a `#[soak(nullable)]` field of one row.
+///
+/// The field's value is stored without its `Option`, alongside a validity bit, so it can be
+/// borrowed as an `Option<&F>` but not as an `&mut Option<F>`.
+pub struct NullableMut<'a, F> {
+    value: *mut F,
+    validity: *mut u8,
+    index: usize,
+    _marker: PhantomData<&'a mut F>,
+}
+
+unsafe impl<F: Send> Send for NullableMut<'_, F> {}
+unsafe impl<F: Sync> Sync for NullableMut<'_, F> {}
+
+impl<'a, F> NullableMut<'a, F> {
+    /// Mutably borrow element `index` of the array at `values`, which is initialized if bit
+    /// `index` of the bitset at `validity` is set.
+    ///
+    /// # Safety
+    ///
+    /// The element and its validity bit must not be otherwise accessed for `'a`.
+    pub unsafe fn from_raw_parts(values: *mut F, validity: *mut u8, index: usize) -> Self {
+        NullableMut { value: values.add(index), validity, index, _marker: PhantomData }
+    }
+
+    /// Check whether the field holds a value.
+    pub fn is_some(&self) -> bool { unsafe { bits::get(self.validity, self.index) } }
+
+    /// Check whether the field is empty.
+    pub fn is_none(&self) -> bool { !self.is_some() }
+
+    /// Borrow the field's value, if it has one.
+    pub fn get(&self) -> Option<&F> {
+        if self.is_some() { unsafe { Some(&*self.value) } } else { None }
+    }
+
+    /// Mutably borrow the field's value, if it has one.
+    pub fn get_mut(&mut self) -> Option<&mut F> {
+        if self.is_some() { unsafe { Some(&mut *self.value) } } else { None }
+    }
+
+    /// Convert into a mutable borrow of the field's value, if it has one.
+    pub fn into_mut(self) -> Option<&'a mut F> {
+        if self.is_some() { unsafe { Some(&mut *self.value) } } else { None }
+    }
+
+    /// Store `value` in the field, returning its old value.
+    pub fn replace(&mut self, value: Option<F>) -> Option<F> {
+        unsafe {
+            let old = if self.is_some() { Some(ptr::read(self.value)) } else { None };
+            match value {
+                Some(value) => {
+                    ptr::write(self.value, value);
+                    bits::set(self.validity, self.index, true);
+                }
+                None => bits::set(self.validity, self.index, false),
+            }
+            old
+        }
+    }
+
+    /// Take the field's value, leaving it empty.
+    pub fn take(&mut self) -> Option<F> { self.replace(None) }
+}
+
+impl<F: fmt::Debug> fmt::Debug for NullableMut<'_, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { self.get().fmt(f) }
+}
+
+/// A borrowed range of a `#[soak(nullable)]` field's column.
+///
+/// Every row of a `#[soak(lazy, nullable)]` column that has not been allocated is empty.
+pub struct NullableColumn<'a, F> {
+    values: *mut F,
+    validity: *mut u8,
+    start: usize,
+    len: usize,
+    _marker: PhantomData<&'a F>,
+}
+
+unsafe impl<F: Sync> Send for NullableColumn<'_, F> {}
+unsafe impl<F: Sync> Sync for NullableColumn<'_, F> {}
+
+impl<F> Clone for NullableColumn<'_, F> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<F> Copy for NullableColumn<'_, F> {}
+
+impl<'a, F> NullableColumn<'a, F> {
+    /// Borrow elements `start..start + len` of the array at `values`, each of which is
+    /// initialized if its bit in the bitset at `validity` is set. If `validity` is null, every
+    /// element is empty.
+    ///
+    /// # Safety
+    ///
+    /// The elements and their validity bits must not be mutated for `'a`.
+    pub unsafe fn from_raw_parts(values: *mut F, validity: *mut u8, start: usize, len: usize)
+        -> Self
+    {
+        NullableColumn { values, validity, start, len, _marker: PhantomData }
+    }
+
+    /// Get the number of rows.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Check whether the column contains no rows.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Check whether the row at `index` holds a value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn is_some(&self, index: usize) -> bool {
+        assert!(index < self.len, "index out of bounds");
+        !self.validity.is_null() && unsafe { bits::get(self.validity, self.start + index) }
+    }
+
+    /// Borrow the value in the row at `index`, if it has one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&'a F> {
+        if !self.is_some(index) {
+            return None;
+        }
+
+        unsafe { Some(&*self.values.add(self.start + index)) }
+    }
+
+    /// Iterate over the rows' values.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = Option<&'a F>> + ExactSizeIterator {
+        let column = *self;
+        (0..self.len).map(move |index| column.get(index))
+    }
+}
+
+impl<F: fmt::Debug> fmt::Debug for NullableColumn<'_, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A mutably borrowed range of a `#[soak(nullable)]` field's column.
+pub struct NullableColumnMut<'a, F> {
+    values: *mut F,
+    validity: *mut u8,
+    start: usize,
+    len: usize,
+    _marker: PhantomData<&'a mut F>,
+}
+
+unsafe impl<F: Send> Send for NullableColumnMut<'_, F> {}
+unsafe impl<F: Sync> Sync for NullableColumnMut<'_, F> {}
+
+impl<'a, F> NullableColumnMut<'a, F> {
+    /// Mutably borrow elements `start..start + len` of the array at `values`, each of which is
+    /// initialized if its bit in the bitset at `validity` is set.
+    ///
+    /// # Safety
+    ///
+    /// The elements and their validity bits must not be otherwise accessed for `'a`.
+    pub unsafe fn from_raw_parts(values: *mut F, validity: *mut u8, start: usize, len: usize)
+        -> Self
+    {
+        NullableColumnMut { values, validity, start, len, _marker: PhantomData }
+    }
+
+    /// Get the number of rows.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Check whether the column contains no rows.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Reborrow the rows as a shared [`NullableColumn`].
+    pub fn as_column(&self) -> NullableColumn<'_, F> {
+        unsafe { NullableColumn::from_raw_parts(self.values, self.validity, self.start, self.len) }
+    }
+
+    /// Check whether the row at `index` holds a value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn is_some(&self, index: usize) -> bool { self.as_column().is_some(index) }
+
+    /// Borrow the value in the row at `index`, if it has one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&F> { self.as_column().get(index) }
+
+    /// Mutably borrow the value in the row at `index`, if it has one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut F> { self.entry(index).into_mut() }
+
+    /// Store `value` in the row at `index`, returning its old value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn replace(&mut self, index: usize, value: Option<F>) -> Option<F> {
+        self.entry(index).replace(value)
+    }
+
+    /// Iterate over the rows' values mutably.
+    pub fn iter_mut(&mut self)
+        -> impl DoubleEndedIterator<Item = NullableMut<'_, F>> + ExactSizeIterator
+    {
+        let NullableColumnMut { values, validity, start, .. } = *self;
+        (start..start + self.len).map(move |index| unsafe {
+            NullableMut::from_raw_parts(values, validity, index)
+        })
+    }
+
+    /// Mutably borrow the row at `index`.
+    fn entry(&mut self, index: usize) -> NullableMut<'_, F> {
+        assert!(index < self.len, "index out of bounds");
+        unsafe { NullableMut::from_raw_parts(self.values, self.validity, self.start + index) }
+    }
+}
+
+impl<F: fmt::Debug> fmt::Debug for NullableColumnMut<'_, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { self.as_column().fmt(f) }
+}
@@ -0,0 +1,62 @@
+use core::{cmp, ptr};
+use core::borrow::Borrow;
+use rayon::prelude::*;
+
+use crate::{Allocator, Columns, FieldSet, Table};
+
+/// Pointers into a table, shared with the worker threads of a parallel iterator.
+///
+/// The iterators below only hand out disjoint rows or ranges, and require their items to be
+/// `Send`, which is what makes sharing the pointers themselves sound.
+#[derive(Copy, Clone)]
+struct Shared<P>(P);
+
+unsafe impl<P> Send for Shared<P> {}
+unsafe impl<P> Sync for Shared<P> {}
+
+impl<P: Copy> Shared<P> {
+    // Closures must capture the whole wrapper, rather than just the pointers inside it.
+    fn get(self) -> P { self.0 }
+}
+
+/// Parallel iteration, with the `rayon` feature.
+impl<T: Columns, A: Allocator> Table<T, A> {
+    /// Iterate over borrowed rows in parallel.
+    pub fn par_iter<'a>(&'a self) -> impl IndexedParallelIterator<Item = T::Ref<'a>> + 'a
+        where T::Ref<'a>: Send
+    {
+        let pointers = Shared::<&[ptr::NonNull<u8>]>(self.raw.pointers.borrow());
+        (0..self.len).into_par_iter().map(move |index| unsafe { T::row(pointers.get(), index) })
+    }
+
+    /// Iterate over mutably borrowed rows in parallel.
+    pub fn par_iter_mut<'a>(&'a mut self) -> impl IndexedParallelIterator<Item = T::Mut<'a>> + 'a
+        where T::Mut<'a>: Send
+    {
+        self.pointers_mut();
+        let pointers = Shared::<&[ptr::NonNull<u8>]>(self.raw.pointers.borrow());
+        (0..self.len).into_par_iter().map(move |index| unsafe { T::row_mut(pointers.get(), index) })
+    }
+
+    /// Iterate in parallel over chunks of several distinct field arrays at once.
+    ///
+    /// Each item holds the same range of rows from every field array, and contains `chunk_size`
+    /// rows except for the last chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero, or if `fields` names the same field more than once or a
+    /// field that is not stored as a plain array.
+    pub fn par_chunks_mut<'a, S>(&'a mut self, fields: S, chunk_size: usize)
+        -> impl IndexedParallelIterator<Item = S::Slices<'a>> + 'a
+        where S: FieldSet<T> + 'a, S::Slices<'a>: Send
+    {
+        assert!(chunk_size != 0, "chunk size must be non-zero");
+        let ptrs = Shared(fields.ptrs(self.raw.pointers.borrow()));
+        let len = self.len;
+        (0..len.div_ceil(chunk_size)).into_par_iter().map(move |chunk| unsafe {
+            let start = chunk * chunk_size;
+            S::slices(ptrs.get(), start, cmp::min(chunk_size, len - start))
+        })
+    }
+}
@@ -0,0 +1,416 @@
+use core::{cmp, slice};
+use core::borrow::{Borrow, BorrowMut};
+use core::marker::PhantomData;
+use core::ops::{Range, RangeBounds};
+use dioptre::Field;
+
+use crate::{bit_array, group_pointers, nullable_arrays, nullable_pointers, plain_array};
+use crate::{Columns, FieldSet, Iter, IterMut};
+use crate::{BitColumn, BitColumnMut, NullableColumn, NullableColumnMut};
+use crate::table::range_bounds;
+
+/// A borrowed range of rows from a [`Table`](crate::Table).
+///
+/// Like `&[T]`, a `TableSlice` is a pointer to each field array, along with the range of rows it
+/// borrows.
+pub struct TableSlice<'a, T: Columns> {
+    pointers: T::Pointers,
+    start: usize,
+    len: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+/// A mutably borrowed range of rows from a [`Table`](crate::Table).
+///
+/// Like `&mut [T]`, a `TableSliceMut` is a pointer to each field array, along with the range of
+/// rows it borrows. Disjoint `TableSliceMut`s can be handed to different threads.
+pub struct TableSliceMut<'a, T: Columns> {
+    pointers: T::Pointers,
+    start: usize,
+    len: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+unsafe impl<T: Columns + Sync> Send for TableSlice<'_, T> {}
+unsafe impl<T: Columns + Sync> Sync for TableSlice<'_, T> {}
+
+unsafe impl<T: Columns + Send> Send for TableSliceMut<'_, T> {}
+unsafe impl<T: Columns + Sync> Sync for TableSliceMut<'_, T> {}
+
+impl<T: Columns> Clone for TableSlice<'_, T> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<T: Columns> Copy for TableSlice<'_, T> {}
+
+impl<'a, T: Columns> TableSlice<'a, T> {
+    /// Borrow rows `start..start + len` of the field arrays at `pointers`.
+    ///
+    /// # Safety
+    ///
+    /// `pointers` must point to field arrays laid out for `T`, with those rows initialized. They
+    /// must not be mutated for `'a`.
+    pub unsafe fn from_raw_parts(pointers: T::Pointers, start: usize, len: usize) -> Self {
+        TableSlice { pointers, start, len, _marker: PhantomData }
+    }
+
+    /// Get the number of rows.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Check whether the slice contains no rows.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Get the elements of a field array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn column<F>(&self, field: Field<T, F>) -> &'a [F] {
+        let array = plain_array::<T>(field.index());
+        unsafe {
+            let data = self.pointers.borrow()[array].as_ptr() as *const F;
+            slice::from_raw_parts(data.add(self.start), self.len)
+        }
+    }
+
+    /// Get the values of a `#[soak(nullable)]` field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not nullable.
+    pub fn nullable<F>(&self, field: Field<T, Option<F>>) -> NullableColumn<'a, F> {
+        let (values, validity) = nullable_pointers::<T, F>(self.pointers.borrow(), field.index());
+        unsafe { NullableColumn::from_raw_parts(values, validity, self.start, self.len) }
+    }
+
+    /// Get the values of a bit-packed `bool` field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as bits.
+    pub fn bits(&self, field: Field<T, bool>) -> BitColumn<'a> {
+        let data = self.pointers.borrow()[bit_array::<T>(field.index())].as_ptr();
+        unsafe { BitColumn::from_raw_parts(data, self.start, self.len) }
+    }
+
+    /// Get the columns of a `#[dioptre(flatten)]` field, as a table of their own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not flattened.
+    pub fn group<F: Columns>(&self, field: Field<T, F>) -> TableSlice<'a, F> {
+        let pointers = group_pointers::<T, F>(self.pointers.borrow(), field);
+        unsafe { TableSlice::from_raw_parts(pointers, self.start, self.len) }
+    }
+
+    /// Borrow the fields of the row at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<T::Ref<'a>> {
+        if index >= self.len {
+            return None;
+        }
+
+        unsafe { Some(T::row(self.pointers.borrow(), self.start + index)) }
+    }
+
+    /// Iterate over borrowed rows.
+    pub fn iter(&self) -> Iter<'a, T> { Iter::new(self.pointers, self.range()) }
+
+    /// Borrow the rows in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or extends past the end of the slice.
+    pub fn slice<R: RangeBounds<usize>>(self, range: R) -> Self {
+        let Range { start, end } = range_bounds(range, self.len);
+        unsafe { Self::from_raw_parts(self.pointers, self.start + start, end - start) }
+    }
+
+    /// Divide the slice into the rows before and after `mid`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid` is greater than the slice's length.
+    pub fn split_at(self, mid: usize) -> (Self, Self) {
+        assert!(mid <= self.len, "mid out of bounds");
+        unsafe {
+            let head = Self::from_raw_parts(self.pointers, self.start, mid);
+            let tail = Self::from_raw_parts(self.pointers, self.start + mid, self.len - mid);
+            (head, tail)
+        }
+    }
+
+    /// Iterate over chunks of `chunk_size` rows, except for the last chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn chunks(self, chunk_size: usize) -> Chunks<'a, T> {
+        assert!(chunk_size != 0, "chunk size must be non-zero");
+        Chunks { rest: self, chunk_size }
+    }
+
+    /// Get the range of rows borrowed from the field arrays.
+    fn range(&self) -> Range<usize> { self.start..self.start + self.len }
+}
+
+impl<'a, T: Columns> IntoIterator for TableSlice<'a, T> {
+    type Item = T::Ref<'a>;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> { self.iter() }
+}
+
+impl<'a, T: Columns> TableSliceMut<'a, T> {
+    /// Mutably borrow rows `start..start + len` of the field arrays at `pointers`.
+    ///
+    /// # Safety
+    ///
+    /// `pointers` must point to field arrays laid out for `T`, with those rows initialized. Unless
+    /// `len` is zero, every `LAZY` array must have been allocated. They must not be otherwise
+    /// accessed for `'a`.
+    pub unsafe fn from_raw_parts(pointers: T::Pointers, start: usize, len: usize) -> Self {
+        TableSliceMut { pointers, start, len, _marker: PhantomData }
+    }
+
+    /// Get the number of rows.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Check whether the slice contains no rows.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Reborrow the rows as a shared [`TableSlice`].
+    pub fn as_slice(&self) -> TableSlice<'_, T> {
+        unsafe { TableSlice::from_raw_parts(self.pointers, self.start, self.len) }
+    }
+
+    /// Reborrow the rows mutably, for a shorter lifetime.
+    pub fn reborrow(&mut self) -> TableSliceMut<'_, T> {
+        unsafe { TableSliceMut::from_raw_parts(self.pointers, self.start, self.len) }
+    }
+
+    /// Get the elements of a field array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn column<F>(&self, field: Field<T, F>) -> &[F] { self.as_slice().column(field) }
+
+    /// Get the elements of a field array, mutably.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn column_mut<F>(&mut self, field: Field<T, F>) -> &mut [F] {
+        let array = plain_array::<T>(field.index());
+        unsafe {
+            let data = self.pointers.borrow_mut()[array].as_ptr() as *mut F;
+            slice::from_raw_parts_mut(data.add(self.start), self.len)
+        }
+    }
+
+    /// Get the values of a `#[soak(nullable)]` field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not nullable.
+    pub fn nullable<F>(&self, field: Field<T, Option<F>>) -> NullableColumn<'_, F> {
+        self.as_slice().nullable(field)
+    }
+
+    /// Get the values of a `#[soak(nullable)]` field, mutably.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not nullable.
+    pub fn nullable_mut<F>(&mut self, field: Field<T, Option<F>>) -> NullableColumnMut<'_, F> {
+        let (values, validity) = nullable_arrays::<T>(field.index());
+        let pointers = self.pointers.borrow();
+        let (values, validity) = (pointers[values].as_ptr() as *mut F, pointers[validity].as_ptr());
+        unsafe { NullableColumnMut::from_raw_parts(values, validity, self.start, self.len) }
+    }
+
+    /// Get the values of a bit-packed `bool` field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as bits.
+    pub fn bits(&self, field: Field<T, bool>) -> BitColumn<'_> { self.as_slice().bits(field) }
+
+    /// Get the values of a bit-packed `bool` field, mutably.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as bits.
+    pub fn bits_mut(&mut self, field: Field<T, bool>) -> BitColumnMut<'_> {
+        let data = self.pointers.borrow()[bit_array::<T>(field.index())].as_ptr();
+        unsafe { BitColumnMut::from_raw_parts(data, self.start, self.len) }
+    }
+
+    /// Get the columns of a `#[dioptre(flatten)]` field, as a table of their own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not flattened.
+    pub fn group<F: Columns>(&self, field: Field<T, F>) -> TableSlice<'_, F> {
+        self.as_slice().group(field)
+    }
+
+    /// Get the columns of a `#[dioptre(flatten)]` field mutably, as a table of their own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not flattened.
+    pub fn group_mut<F: Columns>(&mut self, field: Field<T, F>) -> TableSliceMut<'_, F> {
+        let pointers = group_pointers::<T, F>(self.pointers.borrow(), field);
+        unsafe { TableSliceMut::from_raw_parts(pointers, self.start, self.len) }
+    }
+
+    /// Get the elements of several distinct field arrays, mutably.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fields` names the same field more than once, or a field that is not stored as a
+    /// plain array.
+    pub fn columns_mut<'b, S: FieldSet<T> + 'b>(&'b mut self, fields: S) -> S::Slices<'b> {
+        let ptrs = fields.ptrs(self.pointers.borrow());
+        unsafe { S::slices(ptrs, self.start, self.len) }
+    }
+
+    /// Consume the slice, returning the elements of several distinct field arrays for its whole
+    /// lifetime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fields` names the same field more than once, or a field that is not stored as a
+    /// plain array.
+    pub fn into_columns_mut<S: FieldSet<T> + 'a>(self, fields: S) -> S::Slices<'a> {
+        let ptrs = fields.ptrs(self.pointers.borrow());
+        unsafe { S::slices(ptrs, self.start, self.len) }
+    }
+
+    /// Borrow the fields of the row at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<T::Ref<'_>> {
+        if index >= self.len {
+            return None;
+        }
+
+        unsafe { Some(T::row(self.pointers.borrow(), self.start + index)) }
+    }
+
+    /// Mutably borrow the fields of the row at `index`, or `None` if it is out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<T::Mut<'_>> {
+        if index >= self.len {
+            return None;
+        }
+
+        unsafe { Some(T::row_mut(self.pointers.borrow(), self.start + index)) }
+    }
+
+    /// Iterate over borrowed rows.
+    pub fn iter(&self) -> Iter<'_, T> { Iter::new(self.pointers, self.range()) }
+
+    /// Iterate over mutably borrowed rows.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> { IterMut::new(self.pointers, self.range()) }
+
+    /// Mutably borrow the rows in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or extends past the end of the slice.
+    pub fn slice_mut<R: RangeBounds<usize>>(self, range: R) -> Self {
+        let Range { start, end } = range_bounds(range, self.len);
+        unsafe { Self::from_raw_parts(self.pointers, self.start + start, end - start) }
+    }
+
+    /// Divide the slice into the rows before and after `mid`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid` is greater than the slice's length.
+    pub fn split_at_mut(self, mid: usize) -> (Self, Self) {
+        assert!(mid <= self.len, "mid out of bounds");
+        unsafe {
+            let head = Self::from_raw_parts(self.pointers, self.start, mid);
+            let tail = Self::from_raw_parts(self.pointers, self.start + mid, self.len - mid);
+            (head, tail)
+        }
+    }
+
+    /// Iterate over mutably borrowed chunks of `chunk_size` rows, except for the last chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn chunks_mut(self, chunk_size: usize) -> ChunksMut<'a, T> {
+        assert!(chunk_size != 0, "chunk size must be non-zero");
+        ChunksMut { rest: self, chunk_size }
+    }
+
+    /// Get the range of rows borrowed from the field arrays.
+    fn range(&self) -> Range<usize> { self.start..self.start + self.len }
+}
+
+impl<'a, T: Columns> IntoIterator for TableSliceMut<'a, T> {
+    type Item = T::Mut<'a>;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> { IterMut::new(self.pointers, self.range()) }
+}
+
+/// An iterator over [`TableSlice`]s of a fixed number of rows.
+pub struct Chunks<'a, T: Columns> {
+    rest: TableSlice<'a, T>,
+    chunk_size: usize,
+}
+
+impl<'a, T: Columns> Iterator for Chunks<'a, T> {
+    type Item = TableSlice<'a, T>;
+
+    fn next(&mut self) -> Option<TableSlice<'a, T>> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let mid = cmp::min(self.chunk_size, self.rest.len());
+        let (chunk, rest) = self.rest.split_at(mid);
+        self.rest = rest;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.rest.len().div_ceil(self.chunk_size);
+        (len, Some(len))
+    }
+}
+
+impl<T: Columns> ExactSizeIterator for Chunks<'_, T> {}
+
+/// An iterator over [`TableSliceMut`]s of a fixed number of rows.
+pub struct ChunksMut<'a, T: Columns> {
+    rest: TableSliceMut<'a, T>,
+    chunk_size: usize,
+}
+
+impl<'a, T: Columns> Iterator for ChunksMut<'a, T> {
+    type Item = TableSliceMut<'a, T>;
+
+    fn next(&mut self) -> Option<TableSliceMut<'a, T>> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let mid = cmp::min(self.chunk_size, self.rest.len());
+        let TableSliceMut { pointers, start, len, .. } = self.rest;
+        let rest = unsafe { TableSliceMut::from_raw_parts(pointers, start, len) };
+        let (chunk, rest) = rest.split_at_mut(mid);
+        self.rest = rest;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.rest.len().div_ceil(self.chunk_size);
+        (len, Some(len))
+    }
+}
+
+impl<T: Columns> ExactSizeIterator for ChunksMut<'_, T> {}
@@ -0,0 +1,231 @@
+use core::mem;
+use core::convert::TryFrom;
+use alloc::vec::Vec;
+use dioptre::Field;
+
+use crate::{Allocator, Columns, FieldSet, Global, Iter, IterMut, Table, TableSlice, TableSliceMut};
+
+/// A stable reference to a row of a [`SlotTable`].
+///
+/// Once its row is removed, a handle stays invalid even if its slot is recycled for a new row, as
+/// each reuse of a slot bumps its generation.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Handle {
+    index: u32,
+    generation: u32,
+}
+
+impl Handle {
+    /// Get the index of the handle's slot.
+    pub fn index(&self) -> u32 { self.index }
+
+    /// Get the number of times the handle's slot had been reused when it was created.
+    pub fn generation(&self) -> u32 { self.generation }
+}
+
+/// An entry in a [`SlotTable`]'s indirection array.
+struct Slot {
+    generation: u32,
+    row: Option<usize>,
+}
+
+/// A [`Table`] whose rows are addressed by generational [`Handle`]s rather than by index.
+///
+/// Rows are kept densely packed, so columns can still be iterated over as slices, but removing a
+/// row moves the last row into its place. An indirection array maps each handle to the current
+/// index of its row, and is kept up to date as rows move.
+pub struct SlotTable<T: Columns, A: Allocator = Global> {
+    table: Table<T, A>,
+    handles: Vec<Handle>,
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+}
+
+impl<T: Columns, A: Allocator + Default> Default for SlotTable<T, A> {
+    /// Create an empty `SlotTable` without allocating.
+    fn default() -> Self { Self::from_table(Table::default()) }
+}
+
+impl<T: Columns> SlotTable<T> {
+    /// Create an empty `SlotTable` without allocating.
+    pub fn new() -> Self { Self::default() }
+
+    /// Create an empty `SlotTable` with enough space for `capacity` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_table(Table::with_capacity(capacity))
+    }
+}
+
+impl<T: Columns, A: Allocator> SlotTable<T, A> {
+    /// Create an empty `SlotTable` in `alloc` without allocating.
+    pub fn new_in(alloc: A) -> Self { Self::from_table(Table::new_in(alloc)) }
+
+    /// Create an empty `SlotTable` in `alloc` with enough space for `capacity` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Self::from_table(Table::with_capacity_in(capacity, alloc))
+    }
+
+    /// Wrap an empty table.
+    fn from_table(table: Table<T, A>) -> Self {
+        SlotTable { table, handles: Vec::new(), slots: Vec::new(), free: Vec::new() }
+    }
+
+    /// Get the number of rows.
+    pub fn len(&self) -> usize { self.table.len() }
+
+    /// Check whether the table contains no rows.
+    pub fn is_empty(&self) -> bool { self.table.is_empty() }
+
+    /// Get the number of rows the table can hold without reallocating.
+    pub fn capacity(&self) -> usize { self.table.capacity() }
+
+    /// Ensure that the table contains enough space for `extra` more rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn reserve(&mut self, extra: usize) {
+        self.table.reserve(extra);
+        self.handles.reserve(extra);
+    }
+
+    /// Get the handle of each row, in row order.
+    pub fn handles(&self) -> &[Handle] { &self.handles }
+
+    /// Get the current index of `handle`'s row, or `None` if it has been removed.
+    pub fn row(&self, handle: Handle) -> Option<usize> {
+        let slot = self.slots.get(handle.index as usize)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+
+        slot.row
+    }
+
+    /// Check whether `handle`'s row is still in the table.
+    pub fn contains(&self, handle: Handle) -> bool { self.row(handle).is_some() }
+
+    /// Borrow the fields of `handle`'s row, or `None` if it has been removed.
+    pub fn get(&self, handle: Handle) -> Option<T::Ref<'_>> { self.table.get(self.row(handle)?) }
+
+    /// Mutably borrow the fields of `handle`'s row, or `None` if it has been removed.
+    pub fn get_mut(&mut self, handle: Handle) -> Option<T::Mut<'_>> {
+        let row = self.row(handle)?;
+        self.table.get_mut(row)
+    }
+
+    /// Append a row to the table, returning its handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds [`usize::MAX`] bytes, or if the table already has
+    /// [`u32::MAX`] slots.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn insert(&mut self, row: T) -> Handle {
+        let index = match self.free.last() {
+            Some(&index) => index,
+            None => u32::try_from(self.slots.len()).expect("too many slots"),
+        };
+
+        self.handles.reserve(1);
+        self.table.push(row);
+        if self.free.pop().is_none() {
+            self.slots.push(Slot { generation: 0, row: None });
+        }
+
+        let slot = &mut self.slots[index as usize];
+        slot.row = Some(self.handles.len());
+        let handle = Handle { index, generation: slot.generation };
+        self.handles.push(handle);
+        handle
+    }
+
+    /// Remove `handle`'s row and return it, or `None` if it has already been removed.
+    ///
+    /// The last row is moved into its place.
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        let row = self.row(handle)?;
+        self.release(handle.index);
+
+        self.handles.swap_remove(row);
+        if let Some(moved) = self.handles.get(row) {
+            self.slots[moved.index as usize].row = Some(row);
+        }
+        Some(self.table.swap_remove(row))
+    }
+
+    /// Remove all rows from the table, invalidating their handles.
+    pub fn clear(&mut self) {
+        for handle in mem::take(&mut self.handles) {
+            self.release(handle.index);
+        }
+        self.table.clear();
+    }
+
+    /// Mark slot `index` as unused, invalidating its handle.
+    fn release(&mut self, index: u32) {
+        let slot = &mut self.slots[index as usize];
+        slot.row = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(index);
+    }
+
+    /// Get the elements of a field array, in row order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn column<F>(&self, field: Field<T, F>) -> &[F] { self.table.column(field) }
+
+    /// Get the elements of a field array mutably, in row order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn column_mut<F>(&mut self, field: Field<T, F>) -> &mut [F] { self.table.column_mut(field) }
+
+    /// Get the elements of several distinct field arrays, mutably.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fields` names the same field more than once, or a field that is not stored as a
+    /// plain array.
+    pub fn columns_mut<'a, S: FieldSet<T> + 'a>(&'a mut self, fields: S) -> S::Slices<'a> {
+        self.table.columns_mut(fields)
+    }
+
+    /// Iterate over borrowed rows, in row order.
+    pub fn iter(&self) -> Iter<'_, T> { self.table.iter() }
+
+    /// Iterate over mutably borrowed rows, in row order.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> { self.table.iter_mut() }
+
+    /// Borrow all rows as a [`TableSlice`].
+    pub fn as_slice(&self) -> TableSlice<'_, T> { self.table.as_slice() }
+
+    /// Mutably borrow all rows as a [`TableSliceMut`].
+    pub fn as_mut_slice(&mut self) -> TableSliceMut<'_, T> { self.table.as_mut_slice() }
+}
@@ -0,0 +1,919 @@
+use core::{mem, ptr, slice};
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ops::{Bound, Range, RangeBounds};
+use alloc::{vec, vec::Vec};
+use dioptre::Field;
+
+use crate::{Allocator, ChunksMut, Columns, FieldSet, Global, RawTable, TableSlice, TableSliceMut};
+use crate::{bit_array, group_pointers, handle_reserve, nullable_arrays, plain_array, Storage};
+use crate::{BitColumn, BitColumnMut, NullableColumn, NullableColumnMut, TryReserveError};
+
+/// A growable struct of arrays, similar to [`Vec`].
+///
+/// `Table` builds on [`RawTable`] by tracking how many of its rows are initialized. Rows are moved
+/// into and out of the table whole, and are dropped along with the table.
+///
+/// [`Vec`]: alloc::vec::Vec
+pub struct Table<T: Columns, A: Allocator = Global> {
+    pub(crate) raw: RawTable<T, A>,
+    pub(crate) len: usize,
+}
+
+impl<T: Columns, A: Allocator + Default> Default for Table<T, A> {
+    /// Create an empty `Table` without allocating.
+    fn default() -> Self {
+        Table { raw: RawTable::default(), len: 0 }
+    }
+}
+
+impl<T: Columns> Table<T> {
+    /// Create an empty `Table` without allocating.
+    pub fn new() -> Self { Self::default() }
+
+    /// Create an empty `Table` with enough space for `capacity` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Table { raw: RawTable::with_capacity(capacity), len: 0 }
+    }
+
+    /// Create an empty `Table` with enough space for `capacity` rows, returning an error if the
+    /// allocation fails.
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        Ok(Table { raw: RawTable::try_with_capacity(capacity)?, len: 0 })
+    }
+}
+
+impl<T: Columns, A: Allocator> Table<T, A> {
+    /// Create an empty `Table` in `alloc` without allocating.
+    pub fn new_in(alloc: A) -> Self {
+        Table { raw: RawTable::new_in(alloc), len: 0 }
+    }
+
+    /// Create an empty `Table` in `alloc` with enough space for `capacity` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Table { raw: RawTable::with_capacity_in(capacity, alloc), len: 0 }
+    }
+
+    /// Create an empty `Table` in `alloc` with enough space for `capacity` rows, returning an
+    /// error if the allocation fails.
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        Ok(Table { raw: RawTable::try_with_capacity_in(capacity, alloc)?, len: 0 })
+    }
+
+    /// Get a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A { self.raw.allocator() }
+
+    /// Get the number of initialized rows.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Check whether the table contains no rows.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Get the number of rows the table can hold without reallocating.
+    pub fn capacity(&self) -> usize { self.raw.capacity() }
+
+    /// Get a pointer to a field array.
+    ///
+    /// Only the first [`len`](Table::len) elements of the array are initialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn ptr<F>(&mut self, field: Field<T, F>) -> *mut F { self.raw.ptr(field) }
+
+    /// Get the initialized elements of a field array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn column<F>(&self, field: Field<T, F>) -> &[F] { self.as_slice().column(field) }
+
+    /// Get the initialized elements of a field array, mutably.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn column_mut<F>(&mut self, field: Field<T, F>) -> &mut [F] {
+        unsafe { slice::from_raw_parts_mut(self.raw.ptr(field), self.len) }
+    }
+
+    /// Get the values of a `#[soak(nullable)]` field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not nullable.
+    pub fn nullable<F>(&self, field: Field<T, Option<F>>) -> NullableColumn<'_, F> {
+        self.as_slice().nullable(field)
+    }
+
+    /// Get the values of a `#[soak(nullable)]` field, mutably. A `#[soak(lazy, nullable)]` field's
+    /// arrays are allocated if they have not been already.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not nullable.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn nullable_mut<F>(&mut self, field: Field<T, Option<F>>) -> NullableColumnMut<'_, F> {
+        let index = field.index();
+        handle_reserve(self.raw.try_allocate_arrays(index));
+        let (values, validity) = nullable_arrays::<T>(index);
+        let pointers = self.raw.pointers.borrow();
+        let (values, validity) = (pointers[values].as_ptr() as *mut F, pointers[validity].as_ptr());
+        unsafe { NullableColumnMut::from_raw_parts(values, validity, 0, self.len) }
+    }
+
+    /// Check whether a field's arrays have been allocated, which is only ever not the case for
+    /// `#[soak(lazy)]` fields.
+    pub fn is_allocated<F>(&self, field: Field<T, F>) -> bool { self.raw.is_allocated(field) }
+
+    /// Get the values of a bit-packed `bool` field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as bits.
+    pub fn bits(&self, field: Field<T, bool>) -> BitColumn<'_> { self.as_slice().bits(field) }
+
+    /// Get the values of a bit-packed `bool` field, mutably.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as bits.
+    pub fn bits_mut(&mut self, field: Field<T, bool>) -> BitColumnMut<'_> {
+        let data = self.raw.pointers.borrow()[bit_array::<T>(field.index())].as_ptr();
+        unsafe { BitColumnMut::from_raw_parts(data, 0, self.len) }
+    }
+
+    /// Get the columns of a `#[dioptre(flatten)]` field, as a table of their own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not flattened.
+    pub fn group<F: Columns>(&self, field: Field<T, F>) -> TableSlice<'_, F> {
+        self.as_slice().group(field)
+    }
+
+    /// Get the columns of a `#[dioptre(flatten)]` field mutably, as a table of their own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not flattened.
+    pub fn group_mut<F: Columns>(&mut self, field: Field<T, F>) -> TableSliceMut<'_, F> {
+        let pointers = group_pointers::<T, F>(self.pointers_mut().borrow(), field);
+        unsafe { TableSliceMut::from_raw_parts(pointers, 0, self.len) }
+    }
+
+    /// Borrow the fields of the row at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<T::Ref<'_>> {
+        if index >= self.len {
+            return None;
+        }
+
+        unsafe { Some(T::row(self.raw.pointers.borrow(), index)) }
+    }
+
+    /// Mutably borrow the fields of the row at `index`, or `None` if it is out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<T::Mut<'_>> {
+        if index >= self.len {
+            return None;
+        }
+
+        unsafe { Some(T::row_mut(self.pointers_mut().borrow(), index)) }
+    }
+
+    /// Iterate over borrowed rows.
+    pub fn iter(&self) -> Iter<'_, T> { Iter::new(self.raw.pointers, 0..self.len) }
+
+    /// Iterate over mutably borrowed rows.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> { IterMut::new(self.pointers_mut(), 0..self.len) }
+
+    /// Borrow all rows as a [`TableSlice`].
+    pub fn as_slice(&self) -> TableSlice<'_, T> {
+        unsafe { TableSlice::from_raw_parts(self.raw.pointers, 0, self.len) }
+    }
+
+    /// Mutably borrow all rows as a [`TableSliceMut`].
+    pub fn as_mut_slice(&mut self) -> TableSliceMut<'_, T> {
+        unsafe { TableSliceMut::from_raw_parts(self.pointers_mut(), 0, self.len) }
+    }
+
+    /// Get pointers to the field arrays for mutable views of the rows, first allocating any
+    /// `#[soak(lazy)]` arrays so that the views can give them values.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub(crate) fn pointers_mut(&mut self) -> T::Pointers {
+        if self.len > 0 {
+            self.raw.allocate_all();
+        }
+        self.raw.pointers
+    }
+
+    /// Borrow the rows in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or extends past the end of the table.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> TableSlice<'_, T> {
+        self.as_slice().slice(range)
+    }
+
+    /// Mutably borrow the rows in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or extends past the end of the table.
+    pub fn slice_mut<R: RangeBounds<usize>>(&mut self, range: R) -> TableSliceMut<'_, T> {
+        self.as_mut_slice().slice_mut(range)
+    }
+
+    /// Mutably borrow the rows before and after `mid` separately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid` is greater than the table's length.
+    pub fn split_at_mut(&mut self, mid: usize) -> (TableSliceMut<'_, T>, TableSliceMut<'_, T>) {
+        self.as_mut_slice().split_at_mut(mid)
+    }
+
+    /// Iterate over mutably borrowed chunks of `chunk_size` rows, except for the last chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn chunks_mut(&mut self, chunk_size: usize) -> ChunksMut<'_, T> {
+        self.as_mut_slice().chunks_mut(chunk_size)
+    }
+
+    /// Get the initialized elements of several distinct field arrays, mutably.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fields` names the same field more than once, or a field that is not stored as a
+    /// plain array.
+    pub fn columns_mut<'a, S: FieldSet<T> + 'a>(&'a mut self, fields: S) -> S::Slices<'a> {
+        let ptrs = fields.ptrs(self.raw.pointers.borrow());
+        unsafe { S::slices(ptrs, 0, self.len) }
+    }
+
+    /// Ensure that the table contains enough space for `extra` more rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the requested capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn reserve_exact(&mut self, extra: usize) { self.raw.reserve_exact(self.len, extra) }
+
+    /// Ensure that the table contains enough space for `extra` more rows, growing it
+    /// geometrically to amortize the cost of repeated calls.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn reserve(&mut self, extra: usize) { self.raw.reserve(self.len, extra) }
+
+    /// Ensure that the table contains enough space for `extra` more rows, returning an error if
+    /// the allocation fails.
+    pub fn try_reserve_exact(&mut self, extra: usize) -> Result<(), TryReserveError> {
+        self.raw.try_reserve_exact(self.len, extra)
+    }
+
+    /// Ensure that the table contains enough space for `extra` more rows, growing it
+    /// geometrically and returning an error if the allocation fails.
+    pub fn try_reserve(&mut self, extra: usize) -> Result<(), TryReserveError> {
+        self.raw.try_reserve(self.len, extra)
+    }
+
+    /// Shrink the table's capacity as close as possible to `max(len, capacity)`.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn shrink_to(&mut self, capacity: usize) { self.raw.shrink_to(self.len, capacity) }
+
+    /// Shrink the table's capacity as close as possible to its length.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn shrink_to_fit(&mut self) { self.raw.shrink_to_fit(self.len) }
+
+    /// Append a row to the end of the table.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn push(&mut self, row: T) {
+        self.raw.reserve(self.len, 1);
+        unsafe { self.raw.write(self.len, row); }
+        self.len += 1;
+    }
+
+    /// Remove the last row from the table and return it, or `None` if it is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.len -= 1;
+        unsafe { Some(self.raw.read(self.len)) }
+    }
+
+    /// Replace the row at `index` with `row`, returning the old one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn replace(&mut self, index: usize, row: T) -> T {
+        assert!(index < self.len, "index out of bounds");
+        unsafe { self.raw.replace(index, row) }
+    }
+
+    /// Insert a row at `index`, shifting every later row up by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the table's length, or if the new capacity exceeds
+    /// [`usize::MAX`] bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn insert(&mut self, index: usize, row: T) {
+        assert!(index <= self.len, "index out of bounds");
+        self.raw.reserve(self.len, 1);
+
+        unsafe {
+            self.raw.copy(index, index + 1, self.len - index);
+            self.raw.write(index, row);
+        }
+        self.len += 1;
+    }
+
+    /// Remove and return the row at `index`, shifting every later row down by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "index out of bounds");
+
+        self.len -= 1;
+        unsafe {
+            let row = self.raw.read(index);
+            self.raw.copy(index + 1, index, self.len - index);
+            row
+        }
+    }
+
+    /// Remove and return the row at `index`, replacing it with the last row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "index out of bounds");
+
+        self.len -= 1;
+        unsafe {
+            let row = self.raw.read(index);
+            if index != self.len {
+                self.raw.copy(self.len, index, 1);
+            }
+            row
+        }
+    }
+
+    /// Remove all rows from the table, without affecting its capacity.
+    pub fn clear(&mut self) { self.truncate(0) }
+
+    /// Shorten the table to `len` rows, dropping the rest.
+    ///
+    /// This has no effect if `len` is greater than the table's current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+
+        // Shorten the table first, so a panicking destructor leaks the remaining rows rather than
+        // dropping them twice.
+        let end = mem::replace(&mut self.len, len);
+        if mem::needs_drop::<T>() {
+            for index in len..end {
+                unsafe { drop(self.raw.read(index)); }
+            }
+        }
+    }
+
+    /// Keep only the rows for which `f` returns `true`, preserving their order.
+    pub fn retain<F: FnMut(T::Ref<'_>) -> bool>(&mut self, mut f: F) {
+        /// Closes the gap left by removed rows, even if `f` or a destructor panics.
+        struct Guard<'a, T: Columns, A: Allocator> {
+            table: &'a mut Table<T, A>,
+            len: usize,
+            processed: usize,
+            removed: usize,
+        }
+
+        impl<T: Columns, A: Allocator> Drop for Guard<'_, T, A> {
+            fn drop(&mut self) {
+                let Guard { ref mut table, len, processed, removed } = *self;
+                unsafe { table.raw.copy(processed, processed - removed, len - processed); }
+                table.len = len - removed;
+            }
+        }
+
+        // Hide the rows from the table while they are processed, so a panic leaks them rather
+        // than dropping them twice.
+        let len = mem::replace(&mut self.len, 0);
+        let mut guard = Guard { table: self, len, processed: 0, removed: 0 };
+        while guard.processed < len {
+            let index = guard.processed;
+            let keep = f(unsafe { T::row(guard.table.raw.pointers.borrow(), index) });
+
+            guard.processed += 1;
+            if !keep {
+                guard.removed += 1;
+                unsafe { drop(guard.table.raw.read(index)); }
+            } else if guard.removed > 0 {
+                unsafe { guard.table.raw.copy(index, index - guard.removed, 1); }
+            }
+        }
+    }
+
+    /// Sort the rows by the values in one field array.
+    ///
+    /// This sort is stable, and moves each field array independently rather than reassembling
+    /// rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not stored as a plain array of its own type.
+    pub fn sort_by_key<K: Ord>(&mut self, field: Field<T, K>) {
+        let keys = self.column(field);
+        let mut permutation: Vec<usize> = (0..self.len).collect();
+        permutation.sort_by(|&a, &b| keys[a].cmp(&keys[b]));
+        self.apply_permutation(&permutation);
+    }
+
+    /// Sort the rows with a comparator over borrowed rows.
+    ///
+    /// This sort is stable, and moves each field array independently rather than reassembling
+    /// rows.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+        where F: FnMut(T::Ref<'_>, T::Ref<'_>) -> Ordering
+    {
+        let pointers = self.raw.pointers.borrow();
+        let mut permutation: Vec<usize> = (0..self.len).collect();
+        permutation.sort_by(|&a, &b| unsafe {
+            compare(T::row(pointers, a), T::row(pointers, b))
+        });
+        self.apply_permutation(&permutation);
+    }
+
+    /// Reorder the rows so that row `i` is moved from row `permutation[i]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permutation` is not a permutation of `0..len`.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn apply_permutation(&mut self, permutation: &[usize]) {
+        assert_eq!(permutation.len(), self.len, "permutation has the wrong length");
+        let mut seen = vec![false; self.len];
+        for &index in permutation {
+            assert!(index < self.len && !seen[index], "not a permutation");
+            seen[index] = true;
+        }
+
+        unsafe { self.raw.permute(permutation) }
+    }
+
+    /// Remove the rows in `range` from the table, returning them as an iterator.
+    ///
+    /// Any rows not consumed by the iterator are dropped along with it, and the rows after `range`
+    /// are then shifted down to close the gap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or extends past the end of the table.
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T, A> {
+        let Range { start, end } = range_bounds(range, self.len);
+
+        // Hide the drained rows and the tail from the table until the `Drain` is dropped.
+        let len = mem::replace(&mut self.len, start);
+        Drain { table: self, start, end, tail: end, tail_len: len - end }
+    }
+}
+
+/// Check whether every field of `T` is stored as a plain array, so rows can be transposed one
+/// field array at a time.
+fn is_plain<T: Columns>() -> bool {
+    T::STORAGE.iter().all(|storage| matches!(storage, Storage::Plain { .. }))
+}
+
+/// Resolve `range` against a length of `len`.
+///
+/// # Panics
+///
+/// Panics if the range is decreasing or extends past `len`.
+pub(crate) fn range_bounds<R: RangeBounds<usize>>(range: R, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1).expect("range start overflow"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("range end overflow"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(start <= end, "range is decreasing");
+    assert!(end <= len, "range out of bounds");
+
+    start..end
+}
+
+impl<T: Columns, A: Allocator> Extend<T> for Table<T, A> {
+    /// Append the rows from `iter`.
+    ///
+    /// If `iter` reports an exact length and every field is stored as a plain array, its rows are
+    /// collected into a `Vec` and transposed one field array at a time. Otherwise they are pushed
+    /// one row at a time.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        match iter.size_hint() {
+            (lower, Some(upper)) if lower == upper && is_plain::<T>() => {
+                self.append_vec(iter.collect());
+            }
+            (lower, _) => {
+                self.reserve(lower);
+                for row in iter {
+                    self.push(row);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Columns, A: Allocator + Default> FromIterator<T> for Table<T, A> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut table = Table::new_in(A::default());
+        table.extend(iter);
+        table
+    }
+}
+
+impl<T: Columns, A: Allocator + Default> From<Vec<T>> for Table<T, A> {
+    /// Transpose a `Vec` of rows into a table, one field array at a time.
+    fn from(rows: Vec<T>) -> Self {
+        // Collecting a `Vec`'s own iterator back into a `Vec` reuses its buffer.
+        let mut table = Table::new_in(A::default());
+        table.extend(rows);
+        table
+    }
+}
+
+impl<T: Columns, A: Allocator> Table<T, A> {
+    /// Move `rows` onto the end of the table, one field array at a time.
+    ///
+    /// Every field of `T` must be stored as a plain array.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    fn append_vec(&mut self, mut rows: Vec<T>) {
+        debug_assert!(is_plain::<T>());
+        let len = rows.len();
+        if len == 0 {
+            return;
+        }
+        self.reserve(len);
+        // The rows are copied straight into every array, including any `#[soak(lazy)]` ones.
+        self.raw.allocate_all();
+
+        unsafe {
+            // Take ownership of the rows, leaving `rows` to free only its buffer.
+            rows.set_len(0);
+            let src = rows.as_mut_ptr() as *mut u8;
+
+            let pointers = self.raw.pointers.borrow();
+            for (field, (&size, offset)) in Iterator::zip(T::SIZES.iter(), T::OFFSETS).enumerate() {
+                let dst = pointers[plain_array::<T>(field)].as_ptr().add(self.len * size);
+                let src = src.add(offset(src));
+                for index in 0..len {
+                    let src = src.add(index * mem::size_of::<T>());
+                    ptr::copy_nonoverlapping(src, dst.add(index * size), size);
+                }
+            }
+        }
+
+        self.len += len;
+    }
+}
+
+impl<T: Columns, A: Allocator> From<Table<T, A>> for Vec<T> {
+    fn from(table: Table<T, A>) -> Self { table.into_vec() }
+}
+
+impl<T: Columns, A: Allocator> IntoIterator for Table<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> IntoIter<T, A> {
+        let table = ManuallyDrop::new(self);
+        let raw = unsafe { ptr::read(&table.raw) };
+        IntoIter { raw, start: 0, end: table.len }
+    }
+}
+
+impl<T: Columns, A: Allocator> Table<T, A> {
+    /// Transpose the table into a `Vec` of rows, one field array at a time.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM.
+    pub fn into_vec(mut self) -> Vec<T> {
+        let len = self.len;
+        let mut rows = Vec::with_capacity(len);
+        if len == 0 {
+            return rows;
+        }
+        if !is_plain::<T>() {
+            rows.extend(self);
+            return rows;
+        }
+        // The rows are copied straight out of every array, including any `#[soak(lazy)]` ones.
+        self.raw.allocate_all();
+
+        unsafe {
+            let dst = rows.as_mut_ptr() as *mut u8;
+
+            let pointers = self.raw.pointers.borrow();
+            for (field, (&size, offset)) in Iterator::zip(T::SIZES.iter(), T::OFFSETS).enumerate() {
+                let src = pointers[plain_array::<T>(field)];
+                let dst = dst.add(offset(dst));
+                for index in 0..len {
+                    let dst = dst.add(index * mem::size_of::<T>());
+                    ptr::copy_nonoverlapping(src.as_ptr().add(index * size), dst, size);
+                }
+            }
+
+            // The rows now belong to `rows`, so the table must not drop them.
+            self.len = 0;
+            rows.set_len(len);
+        }
+
+        rows
+    }
+}
+
+impl<T: Columns, A: Allocator> Drop for Table<T, A> {
+    /// Drop the initialized rows. The `RawTable` then frees the underlying buffer.
+    fn drop(&mut self) { self.truncate(0) }
+}
+
+impl<'a, T: Columns, A: Allocator> IntoIterator for &'a Table<T, A> {
+    type Item = T::Ref<'a>;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> { self.iter() }
+}
+
+impl<'a, T: Columns, A: Allocator> IntoIterator for &'a mut Table<T, A> {
+    type Item = T::Mut<'a>;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> { self.iter_mut() }
+}
+
+/// An iterator over the borrowed rows of a [`Table`].
+pub struct Iter<'a, T: Columns> {
+    pointers: T::Pointers,
+    start: usize,
+    end: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+impl<T: Columns> Iter<'_, T> {
+    /// Iterate over the rows in `range` of the arrays at `pointers`.
+    pub(crate) fn new(pointers: T::Pointers, range: Range<usize>) -> Self {
+        Iter { pointers, start: range.start, end: range.end, _marker: PhantomData }
+    }
+}
+
+unsafe impl<T: Columns + Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Columns + Sync> Sync for Iter<'_, T> {}
+
+impl<'a, T: Columns> Iterator for Iter<'a, T> {
+    type Item = T::Ref<'a>;
+
+    fn next(&mut self) -> Option<T::Ref<'a>> {
+        if self.start == self.end {
+            return None;
+        }
+
+        let index = self.start;
+        self.start += 1;
+        unsafe { Some(T::row(self.pointers.borrow(), index)) }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T: Columns> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<T::Ref<'a>> {
+        if self.start == self.end {
+            return None;
+        }
+
+        self.end -= 1;
+        unsafe { Some(T::row(self.pointers.borrow(), self.end)) }
+    }
+}
+
+impl<'a, T: Columns> ExactSizeIterator for Iter<'a, T> {}
+
+/// An iterator over the mutably borrowed rows of a [`Table`].
+pub struct IterMut<'a, T: Columns> {
+    pointers: T::Pointers,
+    start: usize,
+    end: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T: Columns> IterMut<'_, T> {
+    /// Iterate over the rows in `range` of the arrays at `pointers`.
+    pub(crate) fn new(pointers: T::Pointers, range: Range<usize>) -> Self {
+        IterMut { pointers, start: range.start, end: range.end, _marker: PhantomData }
+    }
+}
+
+unsafe impl<T: Columns + Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Columns + Sync> Sync for IterMut<'_, T> {}
+
+impl<'a, T: Columns> Iterator for IterMut<'a, T> {
+    type Item = T::Mut<'a>;
+
+    fn next(&mut self) -> Option<T::Mut<'a>> {
+        if self.start == self.end {
+            return None;
+        }
+
+        let index = self.start;
+        self.start += 1;
+        unsafe { Some(T::row_mut(self.pointers.borrow(), index)) }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T: Columns> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<T::Mut<'a>> {
+        if self.start == self.end {
+            return None;
+        }
+
+        self.end -= 1;
+        unsafe { Some(T::row_mut(self.pointers.borrow(), self.end)) }
+    }
+}
+
+impl<'a, T: Columns> ExactSizeIterator for IterMut<'a, T> {}
+
+/// A draining iterator over a range of rows in a [`Table`].
+pub struct Drain<'a, T: Columns, A: Allocator = Global> {
+    table: &'a mut Table<T, A>,
+    start: usize,
+    end: usize,
+    tail: usize,
+    tail_len: usize,
+}
+
+impl<T: Columns, A: Allocator> Iterator for Drain<'_, T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+
+        let index = self.start;
+        self.start += 1;
+        unsafe { Some(self.table.raw.read(index)) }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T: Columns, A: Allocator> DoubleEndedIterator for Drain<'_, T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+
+        self.end -= 1;
+        unsafe { Some(self.table.raw.read(self.end)) }
+    }
+}
+
+impl<T: Columns, A: Allocator> ExactSizeIterator for Drain<'_, T, A> {}
+
+impl<T: Columns, A: Allocator> Drop for Drain<'_, T, A> {
+    /// Drop any remaining drained rows, then move the tail down to close the gap.
+    fn drop(&mut self) {
+        self.for_each(drop);
+
+        let len = self.table.len;
+        unsafe { self.table.raw.copy(self.tail, len, self.tail_len); }
+        self.table.len = len + self.tail_len;
+    }
+}
+
+/// An iterator that moves rows out of a [`Table`].
+pub struct IntoIter<T: Columns, A: Allocator = Global> {
+    raw: RawTable<T, A>,
+    start: usize,
+    end: usize,
+}
+
+impl<T: Columns, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+
+        let index = self.start;
+        self.start += 1;
+        unsafe { Some(self.raw.read(index)) }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T: Columns, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+
+        self.end -= 1;
+        unsafe { Some(self.raw.read(self.end)) }
+    }
+}
+
+impl<T: Columns, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+
+impl<T: Columns, A: Allocator> Drop for IntoIter<T, A> {
+    /// Drop any remaining rows. The `RawTable` then frees the underlying buffer.
+    fn drop(&mut self) { self.for_each(drop); }
+}
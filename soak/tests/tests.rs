@@ -1,6 +1,16 @@
+// The `layout` test spells out alignment masks as `align - 1`, including for `align = 1`.
+#![allow(clippy::erasing_op)]
+
 use core::{ptr, slice};
+use std::alloc::Layout;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use dioptre::Fields;
-use soak::{Columns, RawTable};
+use soak::archetype::World;
+use soak::{AoSoATable, ChunkedTable, Column, Columns, DynTable, RawTable, SlotTable, Table};
+use soak::TryReserveError;
 
 #[derive(Copy, Clone, Fields, Columns)]
 #[allow(dead_code)]
@@ -42,3 +52,1010 @@ fn layout() {
         }
     }
 }
+
+#[derive(Fields, Columns)]
+struct Entry {
+    id: u32,
+    name: String,
+    owner: Rc<()>,
+}
+
+#[test]
+fn table() {
+    let owner = Rc::new(());
+    let mut table = Table::new();
+    for id in 0..100 {
+        table.push(Entry { id, name: id.to_string(), owner: owner.clone() });
+    }
+    assert_eq!(table.len(), 100);
+    assert!(table.capacity() >= 100);
+    assert_eq!(Rc::strong_count(&owner), 101);
+
+    let entry = table.pop().unwrap();
+    assert_eq!(entry.id, 99);
+    assert_eq!(entry.name, "99");
+    drop(entry);
+    assert_eq!(Rc::strong_count(&owner), 100);
+
+    table.truncate(50);
+    assert_eq!(table.len(), 50);
+    assert_eq!(Rc::strong_count(&owner), 51);
+
+    let entry = table.pop().unwrap();
+    assert_eq!((entry.id, &entry.name[..]), (49, "49"));
+    drop(entry);
+
+    drop(table);
+    assert_eq!(Rc::strong_count(&owner), 1);
+
+    let mut table = Table::with_capacity(8);
+    table.push(Entry { id: 0, name: String::new(), owner: owner.clone() });
+    table.clear();
+    assert!(table.is_empty());
+    assert!(table.pop().is_none());
+    assert_eq!(Rc::strong_count(&owner), 1);
+}
+
+#[test]
+fn rows() {
+    let mut raw: RawTable<Entry> = RawTable::with_capacity(4);
+    unsafe {
+        raw.write(2, Entry { id: 7, name: "seven".into(), owner: Rc::new(()) });
+        assert_eq!(*raw.ptr(Entry::id).add(2), 7);
+        assert_eq!(*raw.ptr(Entry::name).add(2), "seven");
+
+        let old = raw.replace(2, Entry { id: 8, name: "eight".into(), owner: Rc::new(()) });
+        assert_eq!((old.id, &old.name[..]), (7, "seven"));
+
+        let row = raw.read(2);
+        assert_eq!((row.id, &row.name[..]), (8, "eight"));
+    }
+
+    let mut table = Table::new();
+    table.push(Entry { id: 1, name: "one".into(), owner: Rc::new(()) });
+    let old = table.replace(0, Entry { id: 2, name: "two".into(), owner: Rc::new(()) });
+    assert_eq!(old.id, 1);
+    assert_eq!(table.pop().map(|row| row.name), Some("two".into()));
+}
+
+#[test]
+fn columns() {
+    let mut table = Table::new();
+    for i in 0..16 {
+        table.push(Data { x: i as u8, y: 2 * i, z: 3 * i as u64 });
+    }
+
+    for y in table.column_mut(Data::y) {
+        *y += 1;
+    }
+
+    assert_eq!(table.column(Data::x).len(), 16);
+    let y: Vec<_> = (0..16).map(|i| 2 * i + 1).collect();
+    assert_eq!(table.column(Data::y), &y[..]);
+    assert_eq!(table.column(Data::z)[15], 45);
+
+    let empty: Table<Data> = Table::new();
+    assert!(empty.column(Data::z).is_empty());
+}
+
+#[test]
+fn disjoint_columns() {
+    let mut table = Table::new();
+    for i in 0..8 {
+        table.push(Data { x: 1, y: i, z: 0 });
+    }
+
+    let (x, y, z) = table.columns_mut((Data::x, Data::y, Data::z));
+    for ((x, y), z) in Iterator::zip(Iterator::zip(x.iter_mut(), y.iter()), z.iter_mut()) {
+        *z = (*x as u64) + (*y as u64);
+        *x = 0;
+    }
+
+    assert_eq!(table.column(Data::x), &[0; 8]);
+    assert_eq!(table.column(Data::z), &[1, 2, 3, 4, 5, 6, 7, 8]);
+}
+
+#[test]
+#[should_panic(expected = "field borrowed more than once")]
+fn overlapping_columns() {
+    let mut table: Table<Data> = Table::new();
+    let _ = table.columns_mut((Data::y, Data::x, Data::y));
+}
+
+fn describe(entry: EntryRef<'_>) -> String {
+    format!("{}: {}", entry.id, entry.name)
+}
+
+#[test]
+fn row_views() {
+    let mut table = Table::new();
+    for id in 0..4 {
+        table.push(Entry { id, name: id.to_string(), owner: Rc::new(()) });
+    }
+
+    assert_eq!(table.get(2).map(describe), Some("2: 2".to_string()));
+    assert!(table.get(4).is_none());
+
+    let entry = table.get_mut(3).unwrap();
+    *entry.id = 30;
+    entry.name.push('0');
+    assert_eq!(describe(table.get(3).unwrap()), "30: 30");
+
+    for entry in &mut table {
+        *entry.id += 1;
+    }
+    let ids: Vec<_> = table.iter().map(|entry| *entry.id).collect();
+    assert_eq!(ids, [1, 2, 3, 31]);
+    let names: Vec<_> = table.iter().rev().map(|entry| entry.name.as_str()).collect();
+    assert_eq!(names, ["30", "2", "1", "0"]);
+    assert_eq!(table.iter_mut().len(), 4);
+}
+
+#[test]
+fn growth() {
+    let mut raw: RawTable<Data> = RawTable::default();
+    let mut reallocations = 0;
+    for used in 0..1000 {
+        let capacity = raw.capacity();
+        raw.reserve(used, 1);
+        if raw.capacity() != capacity {
+            reallocations += 1;
+        }
+    }
+    assert!(reallocations <= 10);
+
+    let capacity = raw.capacity();
+    raw.reserve(capacity + 1, 1);
+    assert!(raw.capacity() >= capacity + 2);
+
+    let mut table = Table::new();
+    for i in 0..100 {
+        table.push(Data { x: i as u8, y: i, z: i as u64 });
+    }
+    table.truncate(10);
+    table.shrink_to(20);
+    assert_eq!(table.capacity(), 20);
+    table.shrink_to_fit();
+    assert_eq!(table.capacity(), 10);
+    assert_eq!(table.column(Data::y), &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+    table.clear();
+    table.shrink_to_fit();
+    assert_eq!(table.capacity(), 0);
+}
+
+#[test]
+fn fallible_allocation() {
+    let overflow = RawTable::<Data>::try_with_capacity(usize::MAX / 4);
+    assert!(matches!(overflow, Err(TryReserveError::CapacityOverflow)));
+
+    let mut table: Table<Data> = Table::try_with_capacity(4).unwrap();
+    table.push(Data { x: 1, y: 2, z: 3 });
+    assert_eq!(table.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+    assert_eq!(table.try_reserve_exact(usize::MAX / 8), Err(TryReserveError::CapacityOverflow));
+    assert_eq!(table.len(), 1);
+
+    table.try_reserve(100).unwrap();
+    assert!(table.capacity() >= 101);
+    assert_eq!(table.column(Data::z), &[3]);
+}
+
+#[cfg(feature = "quickdry")]
+#[test]
+fn arena() {
+    let arena = quickdry::Arena::default();
+
+    let mut table = Table::new_in(&arena);
+    for i in 0..100 {
+        table.push(Data { x: i as u8, y: i, z: i as u64 });
+    }
+    table.shrink_to_fit();
+
+    let mut raw: RawTable<Data, _> = RawTable::with_capacity_in(16, &arena);
+    unsafe { raw.write(0, Data { x: 1, y: 2, z: 3 }); }
+
+    assert_eq!(table.column(Data::z).iter().sum::<u64>(), 4950);
+    assert_eq!(unsafe { *raw.ptr(Data::y) }, 2);
+}
+
+#[derive(Fields, Columns)]
+#[soak(align = 64)]
+struct Lanes {
+    flag: u8,
+    value: f32,
+    weight: f64,
+}
+
+#[test]
+fn column_alignment() {
+    let mut table: RawTable<Lanes> = RawTable::with_capacity(1);
+    assert_eq!(table.capacity(), 64);
+
+    table.reserve_exact(64, 1);
+    assert_eq!(table.capacity(), 128);
+    assert_eq!(table.ptr(Lanes::flag) as usize % 64, 0);
+    assert_eq!(table.ptr(Lanes::value) as usize % 64, 0);
+    assert_eq!(table.ptr(Lanes::weight) as usize % 64, 0);
+
+    let mut table = Table::new();
+    for i in 0..10 {
+        table.push(Lanes { flag: 1, value: i as f32, weight: 0.5 });
+    }
+    table.shrink_to_fit();
+    assert_eq!(table.capacity(), 64);
+    assert_eq!(table.column(Lanes::value)[9], 9.0);
+}
+
+#[test]
+fn tiles() {
+    let owner = Rc::new(());
+    let mut table: AoSoATable<Entry, 8> = AoSoATable::new();
+    for id in 0..20 {
+        table.push(Entry { id, name: id.to_string(), owner: owner.clone() });
+    }
+    assert_eq!(table.len(), 20);
+    assert_eq!(table.tiles(), 3);
+
+    let chunks: Vec<_> = table.chunks(Entry::id).collect();
+    assert_eq!(chunks, [&[0, 1, 2, 3, 4, 5, 6, 7], &[8, 9, 10, 11, 12, 13, 14, 15]]);
+    assert_eq!(table.lanes(Entry::id, 2), &[16, 17, 18, 19]);
+    assert_eq!(table.lanes(Entry::name, 1)[3], "11");
+
+    for lanes in table.chunks_mut(Entry::id) {
+        for id in lanes.iter_mut() {
+            *id *= 10;
+        }
+    }
+    table.lanes_mut(Entry::id, 2)[0] = 1;
+    assert_eq!(*table.get(9).unwrap().id, 90);
+    assert_eq!(*table.get(16).unwrap().id, 1);
+    *table.get_mut(19).unwrap().id = 2;
+
+    let entry = table.pop().unwrap();
+    assert_eq!((entry.id, &entry.name[..]), (2, "19"));
+    drop(entry);
+    assert_eq!(Rc::strong_count(&owner), 20);
+
+    table.truncate(3);
+    assert_eq!(Rc::strong_count(&owner), 4);
+    drop(table);
+    assert_eq!(Rc::strong_count(&owner), 1);
+
+    // Each field's lanes fit in memory, but a whole tile does not.
+    let mut table: AoSoATable<Data, { usize::MAX / 12 }> = AoSoATable::new();
+    assert_eq!(table.try_reserve(1), Err(TryReserveError::CapacityOverflow));
+}
+
+#[test]
+fn editing() {
+    let owner = Rc::new(());
+    let entry = |id: u32| Entry { id, name: id.to_string(), owner: owner.clone() };
+    let ids = |table: &Table<Entry>| -> Vec<u32> { table.column(Entry::id).to_vec() };
+
+    let mut table = Table::new();
+    table.extend((0..10).map(entry));
+    assert_eq!(ids(&table), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+    table.insert(0, entry(10));
+    table.insert(11, entry(11));
+    table.insert(5, entry(12));
+    assert_eq!(ids(&table), [10, 0, 1, 2, 3, 12, 4, 5, 6, 7, 8, 9, 11]);
+    assert_eq!(table.column(Entry::name)[5], "12");
+
+    assert_eq!(table.remove(5).id, 12);
+    assert_eq!(table.swap_remove(0).id, 10);
+    assert_eq!(ids(&table), [11, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+    table.retain(|entry| entry.id % 2 == 1);
+    assert_eq!(ids(&table), [11, 1, 3, 5, 7, 9]);
+    assert_eq!(Rc::strong_count(&owner), 7);
+
+    let drained: Vec<_> = table.drain(1..3).map(|entry| entry.id).collect();
+    assert_eq!(drained, [1, 3]);
+    assert_eq!(ids(&table), [11, 5, 7, 9]);
+
+    let mut drain = table.drain(..3);
+    assert_eq!(drain.next_back().map(|entry| entry.id), Some(7));
+    drop(drain);
+    assert_eq!(ids(&table), [9]);
+    assert_eq!(table.column(Entry::name), ["9"]);
+    assert_eq!(Rc::strong_count(&owner), 2);
+}
+
+#[test]
+fn sorting() {
+    let mut table = Table::new();
+    for (id, name) in [(3, "c"), (1, "a"), (4, "d"), (1, "b"), (5, "e")].iter() {
+        table.push(Entry { id: *id, name: name.to_string(), owner: Rc::new(()) });
+    }
+
+    table.sort_by_key(Entry::id);
+    assert_eq!(table.column(Entry::id), &[1, 1, 3, 4, 5]);
+    assert_eq!(table.column(Entry::name), ["a", "b", "c", "d", "e"]);
+
+    table.sort_by(|a, b| b.name.cmp(a.name));
+    assert_eq!(table.column(Entry::name), ["e", "d", "c", "b", "a"]);
+
+    table.apply_permutation(&[4, 0, 2, 1, 3]);
+    assert_eq!(table.column(Entry::name), ["a", "e", "c", "d", "b"]);
+    assert_eq!(table.column(Entry::id), &[1, 5, 3, 4, 1]);
+
+    // Each field array is permuted in place, without growing the table.
+    table.shrink_to_fit();
+    let capacity = table.capacity();
+    table.sort_by_key(Entry::id);
+    assert_eq!(table.capacity(), capacity);
+    assert_eq!(table.column(Entry::name), ["a", "b", "c", "d", "e"]);
+
+    let cell = |id| Cell { id, alive: id % 3 == 0, marked: false };
+    let mut cells: Table<Cell> = (0..70).rev().map(cell).collect();
+    cells.sort_by_key(Cell::id);
+    assert!(cells.bits(Cell::alive).iter().enumerate().all(|(id, alive)| alive == (id % 3 == 0)));
+}
+
+#[test]
+#[should_panic(expected = "not a permutation")]
+fn invalid_permutation() {
+    let mut table = Table::new();
+    table.extend((0..3).map(|i| Data { x: i, y: 0, z: 0 }));
+    table.apply_permutation(&[0, 1, 1]);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel() {
+    use rayon::prelude::*;
+
+    let mut table = Table::new();
+    table.extend((0..1000).map(|i| Data { x: 1, y: i, z: 0 }));
+
+    table.par_iter_mut().for_each(|row| *row.z = *row.y as u64 * 2);
+    assert_eq!(table.par_iter().map(|row| *row.z).sum::<u64>(), 999 * 1000);
+
+    table.par_chunks_mut((Data::x, Data::y), 64).for_each(|(x, y)| {
+        assert!(x.len() == y.len() && x.len() <= 64);
+        for (x, y) in Iterator::zip(x.iter_mut(), y.iter()) {
+            *x = (*y % 7) as u8;
+        }
+    });
+    assert_eq!(table.par_chunks_mut((Data::z,), 64).count(), 16);
+    assert_eq!(table.column(Data::x)[100], 2);
+}
+
+#[test]
+fn slices() {
+    let mut table = Table::new();
+    table.extend((0..10).map(|i| Data { x: i as u8, y: i, z: 0 }));
+
+    let (mut head, tail) = table.split_at_mut(4);
+    head.column_mut(Data::z).iter_mut().for_each(|z| *z = 1);
+    for mut chunk in tail.chunks_mut(4) {
+        let len = chunk.len() as u64;
+        let (y, z) = chunk.columns_mut((Data::y, Data::z));
+        for (y, z) in Iterator::zip(y.iter(), z.iter_mut()) {
+            *z = *y as u64 * len;
+        }
+    }
+    assert_eq!(table.column(Data::z), &[1, 1, 1, 1, 16, 20, 24, 28, 16, 18]);
+
+    let slice = table.slice(2..8);
+    assert_eq!(slice.len(), 6);
+    assert_eq!(slice.column(Data::x), &[2, 3, 4, 5, 6, 7]);
+    assert_eq!(*slice.get(1).unwrap().y, 3);
+    let (a, b) = slice.split_at(3);
+    assert_eq!(a.iter().map(|row| *row.y).sum::<u32>(), 2 + 3 + 4);
+    assert_eq!(b.into_iter().map(|row| *row.y).sum::<u32>(), 5 + 6 + 7);
+    assert_eq!(slice.chunks(4).map(|chunk| chunk.len()).collect::<Vec<_>>(), [4, 2]);
+
+    let mut slice = table.slice_mut(..=1);
+    *slice.get_mut(1).unwrap().x = 100;
+    assert_eq!(table.column(Data::x)[..3], [0, 100, 2]);
+}
+
+#[test]
+fn conversions() {
+    let owner = Rc::new(());
+    let entry = |id: u32| Entry { id, name: id.to_string(), owner: owner.clone() };
+    let rows: Vec<_> = (0..10).map(entry).collect();
+
+    let table = Table::<Entry>::from(rows);
+    assert_eq!(table.column(Entry::id), &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    assert_eq!(table.column(Entry::name)[7], "7");
+    assert_eq!(Rc::strong_count(&owner), 11);
+
+    let rows = table.into_vec();
+    assert_eq!(rows.iter().map(|row| row.id).sum::<u32>(), 45);
+    assert_eq!(rows[3].name, "3");
+    assert_eq!(Rc::strong_count(&owner), 11);
+
+    let mut table: Table<Entry> = rows.into_iter().filter(|row| row.id % 3 == 0).collect();
+    assert_eq!(table.column(Entry::id), &[0, 3, 6, 9]);
+    assert_eq!(Rc::strong_count(&owner), 5);
+
+    table.extend((10..12).map(entry));
+    assert_eq!(table.column(Entry::id), &[0, 3, 6, 9, 10, 11]);
+    assert_eq!(table.column(Entry::name)[5], "11");
+    assert_eq!(Rc::strong_count(&owner), 7);
+
+    let mut rows = table.into_iter();
+    assert_eq!(rows.next_back().map(|row| row.name), Some("11".to_string()));
+    assert_eq!(rows.next().map(|row| row.id), Some(0));
+    drop(rows);
+    assert_eq!(Rc::strong_count(&owner), 1);
+
+    assert!(Vec::from(Table::<Data>::from(Vec::new())).is_empty());
+}
+
+#[test]
+fn dynamic_columns() {
+    let owner = Arc::new(());
+    let columns = vec![Column::of::<u8>(), Column::of::<Arc<()>>(), Column::of::<u64>()];
+    let mut table = DynTable::with_capacity(columns, 3);
+    assert_eq!(table.capacity(), 3);
+    assert!(table.columns()[1].needs_drop());
+
+    unsafe {
+        for i in 0..10 {
+            table.reserve(i, 1);
+            table.typed_ptr::<u8>(0).unwrap().add(i).write(i as u8);
+            table.typed_ptr::<Arc<()>>(1).unwrap().add(i).write(owner.clone());
+            table.typed_ptr::<u64>(2).unwrap().add(i).write(i as u64 * 10);
+        }
+        assert!(table.capacity() >= 10);
+        assert_eq!(Arc::strong_count(&owner), 11);
+        assert_eq!(table.ptr(2) as usize % 8, 0);
+
+        assert!(table.typed_ptr::<u32>(0).is_none());
+        assert!(table.column::<u64>(0, 10).is_none());
+        assert_eq!(table.column::<u8>(0, 10).unwrap(), &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        table.drop_in_place(2, 1);
+        table.copy(9, 2, 1);
+        table.column_mut::<u64>(2, 9).unwrap()[0] = 1;
+        assert_eq!(table.column::<u64>(2, 9).unwrap(), &[1, 10, 90, 30, 40, 50, 60, 70, 80]);
+
+        table.shrink_to_fit(9);
+        assert_eq!(table.capacity(), 9);
+        assert_eq!(table.column::<u8>(0, 9).unwrap()[2], 9);
+        table.drop_in_place(0, 9);
+    }
+    assert_eq!(Arc::strong_count(&owner), 1);
+
+    let untyped = unsafe { Column::new(core::alloc::Layout::new::<u32>(), None) };
+    let mut table = DynTable::new(vec![untyped]);
+    assert!(unsafe { table.column::<u32>(0, 0) }.is_none());
+    assert_eq!(table.try_reserve_exact(0, usize::MAX), Err(TryReserveError::CapacityOverflow));
+    table.reserve_exact(5, 1);
+    assert_eq!(table.capacity(), 6);
+
+    // Every column is `Send` and `Sync`, so tables and worlds built from them can be shared.
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<DynTable>();
+    assert_send_sync::<World>();
+}
+
+#[test]
+fn archetypes() {
+    let owner = Arc::new(());
+    let mut world = World::new();
+    let entities: Vec<_> = (0..6).map(|_| world.spawn()).collect();
+    for (i, &entity) in entities.iter().enumerate() {
+        world.insert(entity, i as u32);
+        if i % 2 == 0 {
+            world.insert(entity, owner.clone());
+        }
+    }
+    assert_eq!(world.archetypes().len(), 3);
+    assert!(world.archetypes()[0].is_empty());
+    assert_eq!(Arc::strong_count(&owner), 4);
+
+    let location = world.location(entities[4]).unwrap();
+    let archetype = &world.archetypes()[location.archetype];
+    assert!(archetype.has::<Arc<()>>() && archetype.has::<u32>() && !archetype.has::<u8>());
+    assert_eq!(archetype.column::<u32>().unwrap()[location.row], 4);
+    assert_eq!(archetype.entities()[location.row], entities[4]);
+
+    assert_eq!(world.insert(entities[1], 10u32), Some(1));
+    *world.get_mut::<u32>(entities[3]).unwrap() += 20;
+    assert!(world.remove::<Arc<()>>(entities[0]).is_some());
+    assert!(world.remove::<Arc<()>>(entities[0]).is_none());
+    assert_eq!(Arc::strong_count(&owner), 3);
+
+    assert!(world.despawn(entities[2]));
+    assert!(!world.despawn(entities[2]));
+    assert!(!world.contains(entities[2]));
+    assert_eq!(world.get::<u32>(entities[2]), None);
+    assert_eq!(Arc::strong_count(&owner), 2);
+
+    let recycled = world.spawn();
+    assert_eq!(recycled.index(), entities[2].index());
+    assert_ne!(recycled, entities[2]);
+    assert_eq!(world.len(), 6);
+
+    let values: Vec<_> = entities.iter().map(|&entity| world.get::<u32>(entity).copied()).collect();
+    assert_eq!(values, [Some(0), Some(10), None, Some(23), Some(4), Some(5)]);
+    for &entity in &entities {
+        if let Some(location) = world.location(entity) {
+            assert_eq!(world.archetypes()[location.archetype].entities()[location.row], entity);
+        }
+    }
+
+    world.columns_mut::<u32>().flatten().for_each(|value| *value += 1);
+    assert_eq!(world.get::<u32>(entities[5]), Some(&6));
+    assert_eq!(world.columns_mut::<u8>().count(), 0);
+
+    drop(world);
+    assert_eq!(Arc::strong_count(&owner), 1);
+
+    // A panicking destructor must not leave its row to be dropped again.
+    struct Fuse(Arc<AtomicUsize>);
+    impl Drop for Fuse {
+        fn drop(&mut self) {
+            if self.0.fetch_add(1, Ordering::Relaxed) == 0 {
+                panic!("fuse");
+            }
+        }
+    }
+
+    let drops = Arc::new(AtomicUsize::new(0));
+    let mut world = World::new();
+    let (first, second) = (world.spawn(), world.spawn());
+    world.insert(first, Fuse(drops.clone()));
+    world.insert(second, Fuse(drops.clone()));
+    let despawn = std::panic::AssertUnwindSafe(|| world.despawn(first));
+    assert!(std::panic::catch_unwind(despawn).is_err());
+    assert!(!world.contains(first) && world.get::<Fuse>(second).is_some());
+    drop(world);
+    assert_eq!(drops.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn slots() {
+    let owner = Rc::new(());
+    let entry = |id: u32| Entry { id, name: id.to_string(), owner: owner.clone() };
+    let mut table = SlotTable::new();
+    let handles: Vec<_> = (0..5).map(|id| table.insert(entry(id))).collect();
+    assert_eq!(table.handles(), &handles[..]);
+
+    let removed = table.remove(handles[1]).unwrap();
+    assert_eq!(removed.id, 1);
+    assert!(table.remove(handles[1]).is_none());
+    assert!(table.get(handles[1]).is_none());
+    assert_eq!(table.column(Entry::id), &[0, 4, 2, 3]);
+    assert_eq!(table.row(handles[4]), Some(1));
+    assert_eq!(*table.get(handles[4]).unwrap().name, "4");
+
+    *table.get_mut(handles[3]).unwrap().id = 30;
+    let recycled = table.insert(entry(5));
+    assert_eq!(recycled.index(), handles[1].index());
+    assert_ne!(recycled, handles[1]);
+    assert_eq!(table.iter().map(|row| *row.id).collect::<Vec<_>>(), [0, 4, 2, 30, 5]);
+    for (row, &handle) in table.handles().iter().enumerate() {
+        assert_eq!(table.row(handle), Some(row));
+    }
+
+    drop(removed);
+    assert_eq!(Rc::strong_count(&owner), 6);
+    table.clear();
+    assert!(table.is_empty() && !table.contains(recycled));
+    assert_eq!(Rc::strong_count(&owner), 1);
+}
+
+#[derive(Fields, Columns)]
+struct Reading {
+    time: u32,
+    #[soak(nullable)]
+    value: Option<f32>,
+    #[soak(nullable)]
+    label: Option<Rc<()>>,
+}
+
+#[test]
+fn nullable_columns() {
+    let owner = Rc::new(());
+    let reading = |time: u32| Reading {
+        time,
+        value: if time.is_multiple_of(3) { None } else { Some(time as f32) },
+        label: if time.is_multiple_of(2) { Some(owner.clone()) } else { None },
+    };
+    let mut table: Table<Reading> = (0..100).map(reading).collect();
+    assert_eq!(Rc::strong_count(&owner), 51);
+    assert_eq!(table.get(4).unwrap().value, Some(&4.0));
+    assert_eq!(table.get(6).unwrap().value, None);
+    assert!(table.get(6).unwrap().label.is_some());
+
+    let values = table.nullable(Reading::value);
+    assert_eq!(values.iter().flatten().count(), 66);
+    assert_eq!(values.get(98), Some(&98.0));
+
+    table.nullable_mut(Reading::value).replace(0, Some(-1.0));
+    let mut row = table.get_mut(1).unwrap();
+    assert_eq!(row.value.take(), Some(1.0));
+    assert!(row.label.is_none());
+
+    // Rows move with their validity bits, across word boundaries.
+    table.remove(0);
+    table.insert(70, reading(200));
+    table.swap_remove(10);
+    let times: Vec<_> = table.column(Reading::time).to_vec();
+    for (row, &time) in table.iter().zip(&times) {
+        let expected = reading(time);
+        if time != 1 {
+            assert_eq!(row.value, expected.value.as_ref());
+        }
+        assert_eq!(row.label.is_some(), expected.label.is_some());
+    }
+    assert_eq!(table.get(0).unwrap().value, None);
+
+    let (_, tail) = table.split_at_mut(33);
+    assert_eq!(tail.nullable(Reading::value).get(0), reading(times[33]).value.as_ref());
+    let count = table.nullable(Reading::value).iter().flatten().count();
+    let rows = table.into_vec();
+    assert_eq!(rows.iter().filter(|row| row.value.is_some()).count(), count);
+    drop(rows);
+
+    let mut tiles = AoSoATable::<Reading, 8>::new();
+    (0..20).for_each(|time| tiles.push(reading(time)));
+    assert_eq!(tiles.get(16).unwrap().value, Some(&16.0));
+    assert!(tiles.get(18).unwrap().label.is_some());
+    assert_eq!(tiles.pop().unwrap().value, Some(19.0));
+    drop(tiles);
+    assert_eq!(Rc::strong_count(&owner), 1);
+}
+
+#[test]
+#[should_panic(expected = "field is not stored as a plain array")]
+fn nullable_column_as_plain() {
+    let table = Table::<Reading>::new();
+    table.column(Reading::value);
+}
+
+type Flag = bool;
+
+#[derive(Fields, Columns, PartialEq, Debug)]
+struct Cell {
+    id: u32,
+    alive: bool,
+    #[soak(bits)]
+    marked: Flag,
+}
+
+#[test]
+fn bit_columns() {
+    let cell = |id: u32| Cell { id, alive: id.is_multiple_of(3), marked: id.is_multiple_of(5) };
+    let mut table = Table::new();
+    (0..150).for_each(|id| table.push(cell(id)));
+    assert!(table.get(9).unwrap().alive);
+    assert_eq!(table.bits(Cell::alive).count_ones(), 50);
+    assert_eq!(table.bits(Cell::marked).count_zeros(), 120);
+
+    // Words are shifted to start at the slice's first row and masked past its last row.
+    let slice = table.as_slice().slice(7..140);
+    let words: Vec<usize> = slice.bits(Cell::alive).words().collect();
+    assert_eq!(words.len(), 133usize.div_ceil(usize::BITS as usize));
+    for (row, alive) in slice.bits(Cell::alive).iter().enumerate() {
+        let word = words[row / usize::BITS as usize];
+        assert_eq!(word >> (row % usize::BITS as usize) & 1 == 1, alive);
+    }
+    assert_eq!(words.iter().map(|word| word.count_ones()).sum::<u32>(), 44);
+
+    let mut row = table.get_mut(10).unwrap();
+    assert!(row.marked.replace(false));
+    row.alive.toggle();
+    assert!(table.bits(Cell::alive).get(10));
+    table.bits_mut(Cell::marked).set(11, true);
+    table.slice_mut(100..).bits_mut(Cell::alive).fill(false);
+    table.bits_mut(Cell::alive).iter_mut().take(4).for_each(|mut alive| alive.set(true));
+    assert_eq!(table.bits(Cell::alive).count_ones(), 35 + 2);
+
+    assert_eq!(table.swap_remove(0), Cell { id: 0, alive: true, marked: true });
+    assert_eq!(*table.get(0).unwrap().id, 149);
+    assert!(!table.get(0).unwrap().alive);
+    table.remove(5);
+    let rows = table.into_vec();
+    assert_eq!(rows[4], Cell { id: 4, alive: false, marked: false });
+    assert_eq!(rows[5], Cell { id: 6, alive: true, marked: false });
+    assert_eq!(rows[9], Cell { id: 10, alive: true, marked: false });
+    assert_eq!(rows[10], Cell { id: 11, alive: false, marked: true });
+
+    let mut tiles = AoSoATable::<Cell, 4>::new();
+    (0..10).for_each(|id| tiles.push(cell(id)));
+    assert!(tiles.get(6).unwrap().alive && !tiles.get(7).unwrap().alive);
+    assert_eq!(tiles.pop(), Some(cell(9)));
+}
+
+#[derive(Fields, Columns, PartialEq, Debug)]
+struct Vec2 {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Fields, Columns, PartialEq, Debug)]
+struct Motion {
+    #[dioptre(flatten)]
+    velocity: Vec2,
+    #[soak(nullable)]
+    limit: Option<f32>,
+    stopped: bool,
+}
+
+#[derive(Fields, Columns, PartialEq, Debug)]
+struct Body {
+    id: u32,
+    #[dioptre(flatten)]
+    position: Vec2,
+    #[soak(nullable)]
+    owner: Option<Rc<()>>,
+    #[dioptre(flatten)]
+    motion: Motion,
+}
+
+#[test]
+fn flattened_columns() {
+    let owner = Rc::new(());
+    let body = |id: u32| Body {
+        id,
+        position: Vec2 { x: id as f32, y: -(id as f32) },
+        owner: if id.is_multiple_of(2) { Some(owner.clone()) } else { None },
+        motion: Motion {
+            velocity: Vec2 { x: 1.0, y: 0.0 },
+            limit: if id.is_multiple_of(3) { Some(id as f32) } else { None },
+            stopped: id == 4,
+        },
+    };
+
+    let mut table = Table::new();
+    (0..6).for_each(|id| table.push(body(id)));
+    assert_eq!(Rc::strong_count(&owner), 4);
+    assert_eq!(table.column(Body::position.then(Vec2::x)), &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+    let velocities = Body::motion.then(Motion::velocity).then(Vec2::x);
+    assert_eq!(table.column(velocities), &[1.0; 6]);
+    let limits = table.nullable(Body::motion.then(Motion::limit));
+    assert_eq!(limits.iter().collect::<Vec<_>>(), [Some(&0.0), None, None, Some(&3.0), None, None]);
+    assert_eq!(table.bits(Body::motion.then(Motion::stopped)).count_ones(), 1);
+
+    let row = table.get(3).unwrap();
+    assert_eq!((row.position.y, row.motion.limit), (&-3.0, Some(&3.0)));
+    let mut row = table.get_mut(1).unwrap();
+    *row.motion.velocity.y = 2.0;
+    row.motion.limit.replace(Some(10.0));
+    table.group_mut(Body::position).column_mut(Vec2::y).iter_mut().for_each(|y| *y = 0.0);
+    let motion = table.group(Body::motion);
+    assert_eq!(motion.get(1).unwrap().velocity.y, &2.0);
+    assert_eq!(motion.nullable(Motion::limit).get(1), Some(&10.0));
+
+    let mut expected = body(0);
+    expected.position.y = 0.0;
+    assert_eq!(table.swap_remove(0), expected);
+    assert!(table.get(0).unwrap().owner.is_none());
+    let rows = table.into_vec();
+    let velocity = Vec2 { x: 1.0, y: 0.0 };
+    assert_eq!(rows[0].motion, Motion { velocity, limit: None, stopped: false });
+    assert!(rows[4].motion.stopped);
+    drop((rows, expected));
+    assert_eq!(Rc::strong_count(&owner), 1);
+}
+
+/// An allocator that keeps track of its live allocations, and of how many times it has resized
+/// one.
+#[derive(Default)]
+struct Recording {
+    live: RefCell<Vec<(usize, Layout)>>,
+    reallocs: std::cell::Cell<usize>,
+}
+
+impl Recording {
+    /// Find the live allocation containing `ptr`.
+    fn find<T>(&self, ptr: *const T) -> Option<usize> {
+        let ptr = ptr as usize;
+        let live = self.live.borrow();
+        live.iter().position(|&(start, layout)| (start..start + layout.size()).contains(&ptr))
+    }
+}
+
+unsafe impl soak::Allocator for Recording {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = std::alloc::alloc(layout);
+        self.live.borrow_mut().push((ptr as usize, layout));
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let mut live = self.live.borrow_mut();
+        let index = live.iter().position(|&entry| entry == (ptr as usize, layout)).unwrap();
+        live.remove(index);
+        std::alloc::dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let mut live = self.live.borrow_mut();
+        let index = live.iter().position(|&entry| entry == (ptr as usize, layout)).unwrap();
+        let ptr = std::alloc::realloc(ptr, layout, new_size);
+        live[index] = (ptr as usize, Layout::from_size_align(new_size, layout.align()).unwrap());
+        self.reallocs.set(self.reallocs.get() + 1);
+        ptr
+    }
+}
+
+#[derive(Fields, Columns, PartialEq, Debug)]
+struct Account {
+    balance: u64,
+    active: bool,
+    #[soak(cold)]
+    name: String,
+    #[soak(cold, nullable)]
+    note: Option<u16>,
+    #[soak(cold)]
+    #[dioptre(flatten)]
+    location: Vec2,
+}
+
+fn account(id: u64) -> Account {
+    Account {
+        balance: id * 100,
+        active: id.is_multiple_of(2),
+        name: format!("account {}", id),
+        note: if id.is_multiple_of(3) { Some(id as u16) } else { None },
+        location: Vec2 { x: id as f32, y: 0.0 },
+    }
+}
+
+#[test]
+fn cold_columns() {
+    let alloc = Recording::default();
+    let mut table = Table::new_in(&alloc);
+    (0..10).for_each(|id| table.push(account(id)));
+    table.reserve(100);
+    assert_eq!(alloc.live.borrow().len(), 2);
+
+    let hot = alloc.find(table.ptr(Account::balance)).unwrap();
+    let cold = alloc.find(table.ptr(Account::name)).unwrap();
+    assert_ne!(hot, cold);
+    assert_eq!(alloc.find(table.ptr(Account::location.then(Vec2::y))), Some(cold));
+    assert_eq!(alloc.live.borrow()[hot].1.size(), table.capacity() * 8 + 16);
+
+    assert_eq!(table.column(Account::name)[7], "account 7");
+    assert_eq!(table.nullable(Account::note).get(9), Some(&9));
+    assert_eq!(table.bits(Account::active).count_ones(), 5);
+    assert_eq!(table.remove(4), account(4));
+    table.shrink_to_fit();
+    assert_eq!(table.get(4).unwrap().location.x, &5.0);
+    assert_eq!(table.into_vec().last(), Some(&account(9)));
+    assert!(alloc.live.borrow().is_empty());
+}
+
+#[derive(Fields, Columns, PartialEq, Debug)]
+struct Editor {
+    #[soak(lazy, nullable)]
+    tag: Option<u8>,
+}
+
+#[derive(Fields, Columns, PartialEq, Debug)]
+struct Node {
+    id: u32,
+    #[soak(lazy, nullable)]
+    debug: Option<String>,
+    #[dioptre(flatten)]
+    editor: Editor,
+}
+
+#[derive(Fields, Columns, PartialEq, Debug)]
+struct Probe {
+    id: u32,
+    #[soak(lazy)]
+    trace: u64,
+    #[soak(lazy)]
+    hint: Option<u8>,
+}
+
+#[test]
+fn lazy_columns() {
+    let node = |id: u32| Node { id, debug: None, editor: Editor { tag: None } };
+    let alloc = Recording::default();
+    let mut table = Table::new_in(&alloc);
+    (0..10).for_each(|id| table.push(node(id)));
+    assert_eq!(table.remove(3), node(3));
+    table.shrink_to_fit();
+    assert_eq!(alloc.live.borrow().len(), 1);
+    assert!(!table.is_allocated(Node::debug));
+    assert!(table.nullable(Node::debug).iter().all(|debug| debug.is_none()));
+
+    let mut debug = node(10);
+    debug.debug = Some("debug".to_string());
+    table.push(debug);
+    assert!(table.is_allocated(Node::debug));
+    assert!(!table.is_allocated(Node::editor));
+    assert_eq!(alloc.live.borrow().len(), 3);
+    assert_eq!(table.get(0).unwrap().debug, None);
+    assert_eq!(table.nullable(Node::debug).get(9).map(String::as_str), Some("debug"));
+
+    // Mutable row views allocate every lazy column, so any of them can be given a value.
+    assert_eq!(table.get_mut(2).unwrap().editor.tag.replace(Some(7)), None);
+    assert!(table.is_allocated(Node::editor));
+    table.reserve(100);
+    assert_eq!(alloc.live.borrow().len(), 5);
+    assert_eq!(table.get(2).unwrap().editor.tag, Some(&7));
+
+    let rows = table.into_vec();
+    assert_eq!(rows[2].editor.tag, Some(7));
+    assert_eq!(rows[9].debug.as_deref(), Some("debug"));
+    assert!(alloc.live.borrow().is_empty());
+
+    let mut raw: RawTable<Probe, _> = RawTable::with_capacity_in(16, &alloc);
+    assert!(!raw.is_allocated(Probe::trace));
+    unsafe { raw.ptr(Probe::trace).add(3).write(7) };
+    assert!(raw.is_allocated(Probe::trace));
+    assert_eq!(alloc.live.borrow().len(), 2);
+    drop(raw);
+    assert!(alloc.live.borrow().is_empty());
+
+    let mut probes = Table::new();
+    probes.push(Probe { id: 0, trace: 1, hint: None });
+    probes.column_mut(Probe::trace)[0] += 1;
+    assert_eq!(probes.column(Probe::trace), &[2]);
+    assert_eq!(probes.column(Probe::id), &[0]);
+
+    // Without `nullable`, a lazy `Option` is stored as a plain array of `Option`s.
+    assert_eq!(probes.column(Probe::hint), &[None]);
+
+    // Transposing whole rows writes and reads every array, lazy or not.
+    let probe = |id: u32| Probe { id, trace: id as u64 * 2, hint: Some(id as u8) };
+    let probes = Table::<Probe>::from((0..1000).map(probe).collect::<Vec<_>>());
+    assert_eq!(probes.column(Probe::trace)[999], 1998);
+    assert_eq!(probes.into_vec(), (0..1000).map(probe).collect::<Vec<_>>());
+}
+
+#[test]
+fn growth_in_place() {
+    let alloc = Recording::default();
+    let mut table = Table::new_in(&alloc);
+    (0..5).for_each(|id| table.push(account(id)));
+    let reallocs = alloc.reallocs.get();
+    table.reserve_exact(200);
+    assert_eq!(alloc.reallocs.get(), reallocs + 2);
+    assert_eq!(alloc.live.borrow().len(), 2);
+    assert_eq!(table.bits(Account::active).count_ones(), 3);
+    assert_eq!(table.nullable(Account::note).get(3), Some(&3));
+    assert_eq!(table.get(4).unwrap().location.x, &4.0);
+    assert_eq!(table.into_vec(), (0..5).map(account).collect::<Vec<_>>());
+
+    let mut table = Table::new_in(&alloc);
+    table.push(Node { id: 0, debug: Some("debug".to_string()), editor: Editor { tag: None } });
+    table.reserve_exact(100);
+    assert_eq!(alloc.reallocs.get(), reallocs + 5);
+    assert_eq!(alloc.live.borrow().len(), 3);
+    assert_eq!(table.get(0).unwrap().debug.map(String::as_str), Some("debug"));
+    drop(table);
+    assert!(alloc.live.borrow().is_empty());
+}
+
+#[test]
+fn chunked_tables() {
+    let alloc = Recording::default();
+    let mut table = ChunkedTable::new_in(4, &alloc);
+    assert_eq!(table.push(account(0)), (0, 0));
+    let balances = table.ptr(0, Account::balance);
+    table.extend((1..10).map(account));
+    assert_eq!(table.ptr(0, Account::balance), balances);
+    assert_eq!((table.len(), table.chunk_count(), table.chunk_len(2)), (10, 3, 2));
+    assert_eq!((alloc.live.borrow().len(), alloc.reallocs.get()), (6, 0));
+    assert_eq!(table.get(2, 1).unwrap().name, "account 9");
+    assert!(table.get(2, 2).is_none());
+
+    let runs: Vec<_> = table.column(Account::balance).map(<[u64]>::len).collect();
+    assert_eq!(runs, [4, 4, 2]);
+    table.column_mut(Account::balance).flatten().for_each(|balance| *balance += 1);
+    let chunk = table.chunk(1).unwrap();
+    assert_eq!(chunk.column(Account::balance), &[401, 501, 601, 701]);
+    assert_eq!(chunk.nullable(Account::note).get(2), Some(&6));
+    table.get_mut(0, 2).unwrap().active.toggle();
+    let active = table.chunks().map(|chunk| chunk.bits(Account::active).count_ones());
+    assert_eq!(active.sum::<usize>(), 4);
+    assert_eq!(table.iter().next_back().unwrap().location.x, &9.0);
+
+    assert_eq!(table.pop().map(|account| account.balance), Some(901));
+    assert_eq!(table.pop().map(|account| account.balance), Some(801));
+    assert_eq!((table.chunk_count(), alloc.live.borrow().len()), (2, 4));
+    drop(table);
+    assert!(alloc.live.borrow().is_empty());
+}